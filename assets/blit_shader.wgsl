@@ -0,0 +1,11 @@
+#include "fullscreen.wgsl"
+
+@group(0) @binding(0)
+var source_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(source_texture, source_sampler, in.tex_coords);
+}
@@ -0,0 +1,88 @@
+#include "fullscreen.wgsl"
+
+struct Bloom {
+    // the brightness above which colors start to bloom
+    threshold: f32,
+    // how far below the threshold colors fade into the bloom, rather than cutting off sharply
+    knee: f32,
+}
+
+@group(0) @binding(0)
+var source_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> bloom: Bloom;
+
+fn sample_offset(uv: vec2<f32>, texel: vec2<f32>, x: f32, y: f32) -> vec3<f32> {
+    return textureSampleLevel(source_texture, source_sampler, uv + texel * vec2<f32>(x, y), 0.0).rgb;
+}
+
+// the 13 tap downsampling filter from "Next Generation Post Processing in Call of Duty: Advanced
+// Warfare", which avoids the flickering of a plain box filter
+fn downsample(uv: vec2<f32>) -> vec3<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(source_texture));
+
+    let a = sample_offset(uv, texel, -2.0, -2.0);
+    let b = sample_offset(uv, texel, 0.0, -2.0);
+    let c = sample_offset(uv, texel, 2.0, -2.0);
+    let d = sample_offset(uv, texel, -2.0, 0.0);
+    let e = sample_offset(uv, texel, 0.0, 0.0);
+    let f = sample_offset(uv, texel, 2.0, 0.0);
+    let g = sample_offset(uv, texel, -2.0, 2.0);
+    let h = sample_offset(uv, texel, 0.0, 2.0);
+    let i = sample_offset(uv, texel, 2.0, 2.0);
+    let j = sample_offset(uv, texel, -1.0, -1.0);
+    let k = sample_offset(uv, texel, 1.0, -1.0);
+    let l = sample_offset(uv, texel, -1.0, 1.0);
+    let m = sample_offset(uv, texel, 1.0, 1.0);
+
+    return e * 0.125
+        + (a + c + g + i) * 0.03125
+        + (b + d + f + h) * 0.0625
+        + (j + k + l + m) * 0.125;
+}
+
+// keeps only the parts of the color above the threshold, with a quadratic falloff over the knee
+fn threshold(color: vec3<f32>) -> vec3<f32> {
+    let brightness = max(color.r, max(color.g, color.b));
+    let knee = max(bloom.knee, 1e-5);
+
+    var soft = clamp(brightness - bloom.threshold + knee, 0.0, 2.0 * knee);
+    soft = soft * soft / (4.0 * knee);
+
+    let contribution = max(soft, brightness - bloom.threshold) / max(brightness, 1e-5);
+
+    return color * contribution;
+}
+
+// downsamples the HDR scene into the first level of the bloom chain
+@fragment
+fn fs_prefilter(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(threshold(downsample(in.tex_coords)), 1.0);
+}
+
+// downsamples one level of the bloom chain into the next
+@fragment
+fn fs_downsample(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(downsample(in.tex_coords), 1.0);
+}
+
+// upsamples one level of the bloom chain with a 3x3 tent filter, blended onto the previous level
+@fragment
+fn fs_upsample(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(source_texture));
+    let uv = in.tex_coords;
+
+    let color = sample_offset(uv, texel, 0.0, 0.0) * 4.0
+        + (sample_offset(uv, texel, 0.0, -1.0)
+            + sample_offset(uv, texel, -1.0, 0.0)
+            + sample_offset(uv, texel, 1.0, 0.0)
+            + sample_offset(uv, texel, 0.0, 1.0)) * 2.0
+        + sample_offset(uv, texel, -1.0, -1.0)
+        + sample_offset(uv, texel, 1.0, -1.0)
+        + sample_offset(uv, texel, -1.0, 1.0)
+        + sample_offset(uv, texel, 1.0, 1.0);
+
+    return vec4<f32>(color / 16.0, 1.0);
+}
@@ -0,0 +1,3 @@
+// the camera viewing the world, shared by every shader drawing in world space
+@group(0) @binding(0)
+var<uniform> camera_view_projection: mat4x4<f32>;
@@ -0,0 +1,16 @@
+// conversions between linear colors and display (sRGB) encoded colors, shared by every shader
+// working on final colors
+
+fn encode_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+fn decode_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3<f32>(2.4));
+
+    return select(high, low, color <= vec3<f32>(0.04045));
+}
@@ -0,0 +1,69 @@
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) tex_coords: vec2<f32>,
+    @location(3) color: vec3<f32>,
+}
+
+// every copy of the mesh is an instance, with its transform split into columns
+struct InstanceInput {
+    @location(4) transform_0: vec4<f32>,
+    @location(5) transform_1: vec4<f32>,
+    @location(6) transform_2: vec4<f32>,
+    @location(7) transform_3: vec4<f32>,
+    @location(8) color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) tex_coords: vec2<f32>,
+}
+
+struct Material {
+    base_color: vec4<f32>,
+    emissive: vec3<f32>,
+    metallic: f32,
+    roughness: f32,
+}
+
+#include "camera.wgsl"
+
+@group(1) @binding(0)
+var<uniform> material: Material;
+@group(1) @binding(1)
+var base_color_texture: texture_2d<f32>;
+@group(1) @binding(2)
+var emissive_texture: texture_2d<f32>;
+@group(1) @binding(3)
+var material_sampler: sampler;
+
+@vertex
+fn vs_main(in: VertexInput, instance: InstanceInput) -> VertexOutput {
+    var out: VertexOutput;
+
+    let transform = mat4x4<f32>(
+        instance.transform_0,
+        instance.transform_1,
+        instance.transform_2,
+        instance.transform_3,
+    );
+
+    out.clip_position = camera_view_projection * transform * vec4<f32>(in.position, 1.0);
+    out.color = vec4<f32>(in.color, 1.0) * instance.color;
+    out.tex_coords = in.tex_coords;
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let base_color = material.base_color
+        * in.color
+        * textureSample(base_color_texture, material_sampler, in.tex_coords);
+
+    let emissive = material.emissive
+        * textureSample(emissive_texture, material_sampler, in.tex_coords).rgb;
+
+    return vec4<f32>(base_color.rgb + emissive, base_color.a);
+}
@@ -0,0 +1,27 @@
+// a single particle, simulated by the particle simulation shader and drawn as a billboard
+struct Particle {
+    position: vec3<f32>,
+    age: f32,
+    velocity: vec3<f32>,
+    lifetime: f32,
+}
+
+// the settings of an emitter, along with everything needed to simulate and draw the current frame
+struct Emitter {
+    position: vec3<f32>,
+    spawn_start: u32,
+    direction: vec3<f32>,
+    spawn_count: u32,
+    gravity: vec3<f32>,
+    cone_angle: f32,
+    start_color: vec4<f32>,
+    end_color: vec4<f32>,
+    camera_right: vec3<f32>,
+    delta_time: f32,
+    camera_up: vec3<f32>,
+    size: f32,
+    speed: f32,
+    lifetime: f32,
+    seed: u32,
+    capacity: u32,
+}
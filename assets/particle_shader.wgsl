@@ -0,0 +1,60 @@
+// every particle is an instance, read straight from the buffer the simulation writes into
+struct InstanceInput {
+    @location(0) position: vec3<f32>,
+    @location(1) age: f32,
+    @location(2) velocity: vec3<f32>,
+    @location(3) lifetime: f32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) offset: vec2<f32>,
+}
+
+#include "camera.wgsl"
+#include "particle.wgsl"
+
+@group(1) @binding(0)
+var<uniform> emitter: Emitter;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32, particle: InstanceInput) -> VertexOutput {
+    var out: VertexOutput;
+
+    // dead particles collapse into a single point, so that nothing is drawn
+    if particle.age >= particle.lifetime {
+        out.clip_position = vec4(0.0);
+        return out;
+    }
+
+    // the two triangles making up the billboard
+    var corners = array<vec2<f32>, 6>(
+        vec2(-1.0, -1.0),
+        vec2(1.0, -1.0),
+        vec2(1.0, 1.0),
+        vec2(-1.0, -1.0),
+        vec2(1.0, 1.0),
+        vec2(-1.0, 1.0),
+    );
+
+    let corner = corners[vertex_index];
+
+    // billboards always face the camera
+    let world_position = particle.position
+        + (emitter.camera_right * corner.x + emitter.camera_up * corner.y) * emitter.size * 0.5;
+
+    out.clip_position = camera_view_projection * vec4(world_position, 1.0);
+    out.color = mix(emitter.start_color, emitter.end_color, particle.age / particle.lifetime);
+    out.offset = corner;
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // fades the billboard into a soft disc
+    let falloff = 1.0 - smoothstep(0.5, 1.0, length(in.offset));
+
+    return vec4(in.color.rgb, in.color.a * falloff);
+}
@@ -0,0 +1,66 @@
+#include "particle.wgsl"
+
+@group(0) @binding(0)
+var<storage, read_write> particles: array<Particle>;
+@group(0) @binding(1)
+var<uniform> emitter: Emitter;
+
+// hashes an integer (PCG), used as a source of random numbers
+fn hash(value: u32) -> u32 {
+    let state = value * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+
+    return (word >> 22u) ^ word;
+}
+
+// returns a random number between 0.0 and 1.0, advancing the seed
+fn random(seed: ptr<function, u32>) -> f32 {
+    *seed = hash(*seed);
+
+    return f32(*seed) / 4294967295.0;
+}
+
+// returns a random direction within the cone of the emitter, spread evenly across its cap
+fn cone_direction(seed: ptr<function, u32>) -> vec3<f32> {
+    let axis = normalize(emitter.direction);
+
+    // any vector which isn't parallel to the axis gives a basis around it
+    let helper = select(vec3(0.0, 1.0, 0.0), vec3(1.0, 0.0, 0.0), abs(axis.y) > 0.99);
+    let tangent = normalize(cross(axis, helper));
+    let bitangent = cross(axis, tangent);
+
+    let cos_theta = mix(1.0, cos(emitter.cone_angle), random(seed));
+    let sin_theta = sqrt(1.0 - cos_theta * cos_theta);
+    let phi = 6.28318530718 * random(seed);
+
+    return axis * cos_theta + (tangent * cos(phi) + bitangent * sin(phi)) * sin_theta;
+}
+
+@compute @workgroup_size(64)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+
+    if index >= emitter.capacity {
+        return;
+    }
+
+    var particle = particles[index];
+
+    // particles are spawned into a ring starting at `spawn_start`, replacing the oldest ones
+    let ring_offset = (index + emitter.capacity - emitter.spawn_start) % emitter.capacity;
+
+    if ring_offset < emitter.spawn_count {
+        var seed = hash(index ^ hash(emitter.seed));
+
+        particle.position = emitter.position;
+        particle.velocity = cone_direction(&seed) * emitter.speed;
+        particle.age = 0.0;
+        particle.lifetime = emitter.lifetime;
+    } else if particle.age < particle.lifetime {
+        particle.velocity += emitter.gravity * emitter.delta_time;
+        particle.position += particle.velocity * emitter.delta_time;
+        particle.age += emitter.delta_time;
+    }
+
+    particles[index] = particle;
+}
@@ -0,0 +1,128 @@
+#include "fullscreen.wgsl"
+#include "color.wgsl"
+
+struct Post {
+    // how far the color channels are pulled apart towards the edges, in texture coordinates
+    chromatic_aberration: f32,
+    // how much the color lookup table replaces the original colors
+    color_grading_intensity: f32,
+    // how much the corners are darkened
+    vignette_intensity: f32,
+    // the distance from the center (1.0 being the corners) at which the vignette starts
+    vignette_radius: f32,
+    // the distance over which the vignette fades in
+    vignette_smoothness: f32,
+    // the furthest distance (in texels) FXAA blurs along an edge
+    fxaa_span: f32,
+    // whether the source stores linear colors (sRGB targets), rather than display encoded colors
+    linear: u32,
+}
+
+@group(0) @binding(0)
+var source_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> post: Post;
+@group(0) @binding(3)
+var color_lut: texture_3d<f32>;
+
+fn source(uv: vec2<f32>) -> vec4<f32> {
+    return textureSampleLevel(source_texture, source_sampler, uv, 0.0);
+}
+
+// converts a sampled color into its display encoding, which lookup tables and luma are defined in
+fn to_display(color: vec3<f32>) -> vec3<f32> {
+    return select(color, encode_srgb(color), post.linear != 0u);
+}
+
+fn from_display(color: vec3<f32>) -> vec3<f32> {
+    return select(color, decode_srgb(color), post.linear != 0u);
+}
+
+fn luma(color: vec3<f32>) -> f32 {
+    return dot(to_display(color), vec3<f32>(0.299, 0.587, 0.114));
+}
+
+// the fast approximate anti-aliasing by Timothy Lottes, blurring along edges of high contrast
+@fragment
+fn fs_fxaa(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(source_texture));
+    let uv = in.tex_coords;
+
+    let center = source(uv);
+
+    let luma_nw = luma(source(uv + vec2<f32>(-1.0, -1.0) * texel).rgb);
+    let luma_ne = luma(source(uv + vec2<f32>(1.0, -1.0) * texel).rgb);
+    let luma_sw = luma(source(uv + vec2<f32>(-1.0, 1.0) * texel).rgb);
+    let luma_se = luma(source(uv + vec2<f32>(1.0, 1.0) * texel).rgb);
+    let luma_m = luma(center.rgb);
+
+    let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    var direction = vec2<f32>(
+        (luma_sw + luma_se) - (luma_nw + luma_ne),
+        (luma_nw + luma_sw) - (luma_ne + luma_se),
+    );
+
+    let reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * (0.25 / 8.0), 1.0 / 128.0);
+    let scale = 1.0 / (min(abs(direction.x), abs(direction.y)) + reduce);
+
+    direction = clamp(direction * scale, vec2<f32>(-post.fxaa_span), vec2<f32>(post.fxaa_span))
+        * texel;
+
+    let near = 0.5 * (source(uv + direction * (1.0 / 3.0 - 0.5)).rgb
+        + source(uv + direction * (2.0 / 3.0 - 0.5)).rgb);
+    let far = near * 0.5 + 0.25 * (source(uv - direction * 0.5).rgb
+        + source(uv + direction * 0.5).rgb);
+
+    // the wider blur is only kept if it doesn't overshoot the local contrast
+    let luma_far = luma(far);
+    let color = select(far, near, luma_far < luma_min || luma_far > luma_max);
+
+    return vec4<f32>(color, center.a);
+}
+
+// offsets the red and blue channels away from the center, as with a cheap lens
+@fragment
+fn fs_chromatic_aberration(in: VertexOutput) -> @location(0) vec4<f32> {
+    let uv = in.tex_coords;
+    let offset = (uv - 0.5) * post.chromatic_aberration;
+
+    let center = source(uv);
+
+    return vec4<f32>(source(uv + offset).r, center.g, source(uv - offset).b, center.a);
+}
+
+// maps every color through a 3D lookup table, indexed by display encoded colors
+@fragment
+fn fs_color_grading(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = source(in.tex_coords);
+    let display = clamp(to_display(color.rgb), vec3<f32>(0.0), vec3<f32>(1.0));
+
+    // samples the centers of the outermost texels at 0.0 and 1.0
+    let size = f32(textureDimensions(color_lut).x);
+    let coords = display * ((size - 1.0) / size) + 0.5 / size;
+
+    let graded = textureSampleLevel(color_lut, source_sampler, coords, 0.0).rgb;
+    let blended = mix(display, graded, post.color_grading_intensity);
+
+    return vec4<f32>(from_display(blended), color.a);
+}
+
+// darkens the image towards its corners
+@fragment
+fn fs_vignette(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = source(in.tex_coords);
+
+    // scaled so that the corners are at a distance of 1.0
+    let distance = length(in.tex_coords - 0.5) * sqrt(2.0);
+    let amount = smoothstep(
+        post.vignette_radius,
+        post.vignette_radius + post.vignette_smoothness,
+        distance,
+    );
+
+    return vec4<f32>(color.rgb * (1.0 - post.vignette_intensity * amount), color.a);
+}
@@ -0,0 +1,96 @@
+#include "fullscreen.wgsl"
+#include "color.wgsl"
+
+struct Tonemap {
+    // the linear factor every color is scaled by before tonemapping
+    exposure: f32,
+    // 0 for ACES, 1 for Reinhard and 2 for AgX
+    curve: u32,
+    // whether the target stores colors without sRGB encoding them itself
+    encode_srgb: u32,
+}
+
+@group(0) @binding(0)
+var hdr_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var hdr_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> tonemap: Tonemap;
+
+// the fit of the ACES filmic curve by Krzysztof Narkowicz
+fn aces(color: vec3<f32>) -> vec3<f32> {
+    let numerator = color * (2.51 * color + 0.03);
+    let denominator = color * (2.43 * color + 0.59) + 0.14;
+
+    return clamp(numerator / denominator, vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+fn reinhard(color: vec3<f32>) -> vec3<f32> {
+    return color / (1.0 + color);
+}
+
+// the polynomial fit of the AgX base contrast curve by Benjamin Wrensch
+fn agx_contrast(x: vec3<f32>) -> vec3<f32> {
+    let x2 = x * x;
+    let x4 = x2 * x2;
+
+    return 15.5 * x4 * x2
+        - 40.14 * x4 * x
+        + 31.96 * x4
+        - 6.868 * x2 * x
+        + 0.4298 * x2
+        + 0.1191 * x
+        - 0.00232;
+}
+
+fn agx(color: vec3<f32>) -> vec3<f32> {
+    let inset = mat3x3<f32>(
+        vec3<f32>(0.842479062253094, 0.0423282422610123, 0.0423756549057051),
+        vec3<f32>(0.0784335999999992, 0.878468636469772, 0.0784336),
+        vec3<f32>(0.0792237451477643, 0.0791661274605434, 0.879142973793104),
+    );
+
+    let outset = mat3x3<f32>(
+        vec3<f32>(1.19687900512017, -0.0528968517574562, -0.0529716355144438),
+        vec3<f32>(-0.0980208811401368, 1.15190312990417, -0.0980434501171241),
+        vec3<f32>(-0.0990297440797205, -0.0989611768448433, 1.15107367264116),
+    );
+
+    let min_ev = -12.47393;
+    let max_ev = 4.026069;
+
+    var x = inset * color;
+
+    x = clamp(log2(max(x, vec3<f32>(1e-10))), vec3<f32>(min_ev), vec3<f32>(max_ev));
+    x = (x - min_ev) / (max_ev - min_ev);
+    x = outset * agx_contrast(x);
+
+    // the curve produces display encoded colors, which are linearized again
+    return pow(max(x, vec3<f32>(0.0)), vec3<f32>(2.2));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let hdr = textureSample(hdr_texture, hdr_sampler, in.tex_coords);
+    let exposed = max(hdr.rgb * tonemap.exposure, vec3<f32>(0.0));
+
+    var color: vec3<f32>;
+
+    switch tonemap.curve {
+        case 1u: {
+            color = reinhard(exposed);
+        }
+        case 2u: {
+            color = agx(exposed);
+        }
+        default: {
+            color = aces(exposed);
+        }
+    }
+
+    if tonemap.encode_srgb != 0u {
+        color = encode_srgb(color);
+    }
+
+    return vec4<f32>(color, 1.0);
+}
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
-use glam::vec3;
+use glam::{Vec3, Vec4, vec3};
+use web_time::{SystemTime, UNIX_EPOCH};
 #[cfg(target_arch = "wasm32")]
 use winit::event_loop::EventLoopProxy;
 
@@ -9,15 +10,29 @@ use winit::{
     dpi::PhysicalSize,
     event::{DeviceEvent, DeviceId, WindowEvent},
     event_loop::ActiveEventLoop,
+    keyboard::KeyCode,
     window::{Window, WindowId},
 };
 
 use crate::{
     input::InputState,
-    renderer::{Renderer, camera::Camera},
+    renderer::{
+        Renderer, camera::Camera, capture::FrameCapture, config::RendererConfig,
+        material::MaterialId, mesh::MeshData, model::Model, particles::EmitterSettings,
+        post::PostEffect, tonemap::TonemapOperator,
+    },
     timer::FrameTimer,
 };
 
+/// The key which captures the current frame to a PNG file.
+const CAPTURE_KEY: KeyCode = KeyCode::F12;
+
+/// The directory captured frames are written into.
+const CAPTURE_DIRECTORY: &str = "captures";
+
+/// The maximum number of particles alive at once in the emitter of the initial scene.
+const PARTICLE_CAPACITY: u32 = 4096;
+
 /// Manages all subsystems and handles incoming events.
 pub struct App {
     /// The primary window being rendered onto.
@@ -31,6 +46,8 @@ pub struct App {
     input: InputState,
     /// The timer keeping track of frame durations.
     timer: FrameTimer,
+    /// Whether rendering is paused, as the window has a zero dimension (usually when minimized).
+    paused: bool,
 
     /// The state of the UI context.
     ui_context: egui::Context,
@@ -40,19 +57,60 @@ pub struct App {
 
 impl App {
     /// Creates a new [`App`], targetting the given window.
-    pub async fn new(window: Arc<Window>) -> Self {
-        let renderer = Renderer::new(Arc::clone(&window)).await.unwrap();
+    ///
+    /// Fails if no renderer can be created for the window, such as when the adapter lacks the
+    /// features or limits the configuration requires.
+    pub async fn new(window: Arc<Window>) -> anyhow::Result<Self> {
+        #[allow(unused_mut)]
+        let mut config = RendererConfig::from_env();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let adapters = config.available_adapters();
+
+            for (index, info) in adapters.iter().enumerate() {
+                log::info!("adapter #{index}: {} ({:?})", info.name, info.backend);
+            }
+
+            // a mistyped selection shouldn't keep the app from starting
+            if let Some(selection) = config.clear_unavailable_adapter(&adapters) {
+                let available = adapters
+                    .iter()
+                    .enumerate()
+                    .map(|(index, info)| format!("#{index} {}", info.name))
+                    .collect::<Vec<_>>();
+
+                log::warn!(
+                    "no {selection} is available (available: {}), using the default adapter",
+                    available.join(", ")
+                );
+            }
+        }
+
+        let mut renderer = Renderer::new(Arc::clone(&window), config).await?;
+
+        let info = renderer.adapter_info();
+        log::info!("rendering with {} ({:?})", info.name, info.backend);
+
+        Self::load_scene(&mut renderer);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Err(e) = renderer.watch_shaders(concat!(env!("CARGO_MANIFEST_DIR"), "/assets")) {
+            log::warn!("shader hot reloading is disabled: {e:#}");
+        }
 
-        let camera = Camera {
+        let mut camera = Camera {
             position: vec3(0.0, 0.0, 2.0),
             yaw: 0.0,
             pitch: 0.0,
             fov: 45.0f32.to_radians(),
-            aspect_ratio: 0.0,
+            aspect_ratio: 1.0,
             movement_sensitivity: 2.0,
             mouse_sensitivity: 0.005,
         };
 
+        camera.resize(window.inner_size());
+
         let input = InputState::new(Arc::clone(&window));
         let timer = FrameTimer::new();
 
@@ -66,14 +124,43 @@ impl App {
             None,
         );
 
-        Self {
+        Ok(Self {
             window,
             renderer,
             camera,
             input,
             timer,
+            paused: false,
             ui_context,
             ui_input,
+        })
+    }
+
+    /// Uploads the initial scene, made of every model passed on the command line (or a single
+    /// triangle if there are none) and a particle fountain below it.
+    fn load_scene(renderer: &mut Renderer) {
+        let paths = std::env::args().skip(1).collect::<Vec<_>>();
+
+        if paths.is_empty() {
+            renderer.upload_mesh(&MeshData::triangle(), MaterialId::DEFAULT);
+        }
+
+        renderer.add_emitter(
+            EmitterSettings {
+                position: vec3(0.0, -0.5, 0.0),
+                ..Default::default()
+            },
+            PARTICLE_CAPACITY,
+        );
+
+        for path in paths {
+            match Model::load(&path) {
+                Ok(model) => {
+                    let meshes = renderer.upload_model(&model);
+                    log::info!("loaded {} meshes from {path}", meshes.len());
+                }
+                Err(e) => log::error!("failed to load model {path}: {e:#}"),
+            }
         }
     }
 
@@ -90,7 +177,12 @@ impl App {
 
             WindowEvent::CloseRequested => event_loop.exit(),
 
-            WindowEvent::RedrawRequested => self.update(),
+            WindowEvent::RedrawRequested => {
+                if let Err(e) = self.update() {
+                    log::error!("{e:#}");
+                    event_loop.exit();
+                }
+            }
 
             _ => {}
         }
@@ -105,10 +197,18 @@ impl App {
         }
     }
 
-    /// Runs the render and update cycle of the app.
-    fn update(&mut self) {
+    /// Runs the render and update cycle of the app, failing if rendering can't continue.
+    fn update(&mut self) -> anyhow::Result<()> {
+        // no more redraws are requested until the window is resized back
+        if self.paused {
+            return Ok(());
+        }
+
         self.timer.tick();
 
+        #[cfg(not(target_arch = "wasm32"))]
+        self.renderer.reload_changed_shaders();
+
         let dt = self.timer.dt.as_secs_f32();
 
         if self.input.focused {
@@ -116,6 +216,12 @@ impl App {
                 .update_position(|k| self.input.keys_held.contains(k), dt);
         }
 
+        if self.input.keys_pressed.contains(&CAPTURE_KEY) {
+            self.renderer.request_capture();
+        }
+
+        self.renderer.update_particles(dt);
+
         let ui = self
             .ui_context
             .clone()
@@ -129,9 +235,20 @@ impl App {
         self.renderer
             .render(&self.camera, &self.ui_context, ui, || {
                 self.window.pre_present_notify()
-            });
+            })?;
+
+        if let Some(capture) = self.renderer.take_capture() {
+            Self::save_capture(&capture);
+        }
 
+        if let Some(dot) = self.renderer.take_graph_dump() {
+            Self::save_graph_dump(&dot);
+        }
+
+        self.input.end_frame();
         self.window.request_redraw();
+
+        Ok(())
     }
 
     /// Renders all application UI.
@@ -144,11 +261,314 @@ impl App {
                 "Frame Time: {:.2}ms",
                 self.timer.dt.as_secs_f32() * 1000.0
             ));
+
+            let surface_errors = self.renderer.surface_errors();
+
+            if surface_errors.total() > 0 {
+                ui.label(format!(
+                    "Surface Errors: {} lost, {} outdated, {} timed out, {} other",
+                    surface_errors.lost,
+                    surface_errors.outdated,
+                    surface_errors.timeout,
+                    surface_errors.other,
+                ));
+            }
+
+            ui.label(format!(
+                "Surface Format: {:?}",
+                self.renderer.surface_config.format
+            ));
+
+            let mut present_mode = self.renderer.surface_config.present_mode;
+
+            ComboBox::from_label("Present Mode")
+                .selected_text(format!("{present_mode:?}"))
+                .show_ui(ui, |ui| {
+                    for &mode in self.renderer.present_modes() {
+                        ui.selectable_value(&mut present_mode, mode, format!("{mode:?}"));
+                    }
+                });
+
+            if present_mode != self.renderer.surface_config.present_mode {
+                self.renderer.set_present_mode(present_mode);
+            }
+
+            let mut sample_count = self.renderer.sample_count();
+
+            ComboBox::from_label("MSAA")
+                .selected_text(format!("{sample_count}x"))
+                .show_ui(ui, |ui| {
+                    for &count in self.renderer.sample_counts() {
+                        ui.selectable_value(&mut sample_count, count, format!("{count}x"));
+                    }
+                });
+
+            if sample_count != self.renderer.sample_count() {
+                self.renderer.set_sample_count(sample_count);
+            }
+
+            let tonemap = &mut self.renderer.tonemap_settings;
+
+            ComboBox::from_label("Tonemapping")
+                .selected_text(tonemap.operator.name())
+                .show_ui(ui, |ui| {
+                    for operator in TonemapOperator::ALL {
+                        ui.selectable_value(&mut tonemap.operator, operator, operator.name());
+                    }
+                });
+
+            ui.add(
+                Slider::new(&mut tonemap.exposure, -8.0..=8.0)
+                    .text("Exposure")
+                    .suffix(" EV"),
+            );
+
+            if ui.button("Capture Frame").clicked() {
+                self.renderer.request_capture();
+            }
+
+            if ui.button("Dump Render Graph").clicked() {
+                self.renderer.request_graph_dump();
+            }
+        });
+
+        Window::new("Post Processing")
+            .default_open(false)
+            .show(ui, |ui| self.post_ui(ui));
+
+        Window::new("Particles")
+            .default_open(false)
+            .show(ui, |ui| self.particles_ui(ui));
+
+        Window::new("Adapter")
+            .default_open(false)
+            .show(ui, |ui| self.adapter_ui(ui));
+
+        let mut shader_errors = self.renderer.shader_errors().peekable();
+
+        if shader_errors.peek().is_some() {
+            Window::new("Shader Error")
+                .anchor(Align2::CENTER_BOTTOM, vec2(0.0, -16.0))
+                .resizable(false)
+                .collapsible(false)
+                .show(ui, |ui| {
+                    for error in shader_errors {
+                        ui.colored_label(
+                            Color32::LIGHT_RED,
+                            format!("{} failed to compile:", error.kind.file_name()),
+                        );
+                        ui.label(RichText::new(&error.message).monospace());
+                    }
+                });
+        }
+    }
+
+    /// Displays a toggle and the parameters of every post-processing effect.
+    fn post_ui(&mut self, ui: &mut egui::Ui) {
+        use egui::*;
+
+        let settings = &mut self.renderer.post_settings;
+
+        ui.checkbox(&mut settings.bloom.enabled, "Bloom");
+        ui.add_enabled_ui(settings.bloom.enabled, |ui| {
+            ui.add(Slider::new(&mut settings.bloom.threshold, 0.0..=4.0).text("Threshold"));
+            ui.add(Slider::new(&mut settings.bloom.knee, 0.0..=2.0).text("Knee"));
+            ui.add(Slider::new(&mut settings.bloom.intensity, 0.0..=2.0).text("Intensity"));
+        });
+
+        ui.separator();
+
+        ui.checkbox(&mut settings.fxaa.enabled, PostEffect::Fxaa.name());
+        ui.add_enabled_ui(settings.fxaa.enabled, |ui| {
+            ui.add(Slider::new(&mut settings.fxaa.span, 1.0..=16.0).text("Span"));
+        });
+
+        ui.separator();
+
+        let chromatic_aberration = &mut settings.chromatic_aberration;
+
+        ui.checkbox(
+            &mut chromatic_aberration.enabled,
+            PostEffect::ChromaticAberration.name(),
+        );
+        ui.add_enabled_ui(chromatic_aberration.enabled, |ui| {
+            ui.add(Slider::new(&mut chromatic_aberration.strength, 0.0..=0.05).text("Strength"));
+        });
+
+        ui.separator();
+
+        ui.checkbox(
+            &mut settings.color_grading.enabled,
+            PostEffect::ColorGrading.name(),
+        );
+        ui.add_enabled_ui(settings.color_grading.enabled, |ui| {
+            ui.add(Slider::new(&mut settings.color_grading.intensity, 0.0..=1.0).text("Intensity"));
+        });
+
+        ui.separator();
+
+        ui.checkbox(&mut settings.vignette.enabled, PostEffect::Vignette.name());
+        ui.add_enabled_ui(settings.vignette.enabled, |ui| {
+            ui.add(Slider::new(&mut settings.vignette.intensity, 0.0..=1.0).text("Intensity"));
+            ui.add(Slider::new(&mut settings.vignette.radius, 0.0..=1.5).text("Radius"));
+            ui.add(Slider::new(&mut settings.vignette.smoothness, 0.0..=1.0).text("Smoothness"));
+        });
+    }
+
+    /// Displays the settings of every particle emitter, applied from the next frame on.
+    fn particles_ui(&mut self, ui: &mut egui::Ui) {
+        use egui::*;
+
+        for (i, emitter) in self.renderer.emitters_mut().enumerate() {
+            let capacity = emitter.capacity();
+            let settings = &mut emitter.settings;
+
+            CollapsingHeader::new(format!("Emitter {i}"))
+                .default_open(i == 0)
+                .show(ui, |ui| {
+                    Self::vec3_ui(ui, "Position", &mut settings.position);
+                    Self::vec3_ui(ui, "Direction", &mut settings.direction);
+                    Self::vec3_ui(ui, "Gravity", &mut settings.gravity);
+
+                    ui.horizontal(|ui| {
+                        ui.drag_angle(&mut settings.cone_angle);
+                        ui.label("Cone Angle");
+                    });
+
+                    settings.cone_angle = settings.cone_angle.clamp(0.0, std::f32::consts::PI);
+
+                    ui.add(Slider::new(&mut settings.speed, 0.0..=20.0).text("Speed"));
+                    ui.add(
+                        Slider::new(&mut settings.spawn_rate, 0.0..=capacity as f32)
+                            .text("Spawn Rate")
+                            .suffix("/s"),
+                    );
+                    ui.add(
+                        Slider::new(&mut settings.lifetime, 0.1..=10.0)
+                            .text("Lifetime")
+                            .suffix("s"),
+                    );
+                    ui.add(Slider::new(&mut settings.size, 0.01..=1.0).text("Size"));
+
+                    Self::color_ui(ui, "Start Color", &mut settings.start_color);
+                    Self::color_ui(ui, "End Color", &mut settings.end_color);
+                });
+        }
+    }
+
+    /// Displays a labelled row of drag values editing every component of a vector.
+    fn vec3_ui(ui: &mut egui::Ui, label: &str, value: &mut Vec3) {
+        ui.horizontal(|ui| {
+            for component in value.as_mut() {
+                ui.add(egui::DragValue::new(component).speed(0.05));
+            }
+
+            ui.label(label);
+        });
+    }
+
+    /// Displays a labelled button editing a linear color with alpha.
+    fn color_ui(ui: &mut egui::Ui, label: &str, color: &mut Vec4) {
+        ui.horizontal(|ui| {
+            let mut rgba = color.to_array();
+
+            if ui.color_edit_button_rgba_unmultiplied(&mut rgba).changed() {
+                *color = Vec4::from_array(rgba);
+            }
+
+            ui.label(label);
         });
     }
 
-    /// Resizes the state of the app to match the new window size.
+    /// Displays the info and limits of the adapter being rendered with.
+    fn adapter_ui(&self, ui: &mut egui::Ui) {
+        use egui::*;
+
+        let info = self.renderer.adapter_info();
+
+        Grid::new("adapter_info").striped(true).show(ui, |ui| {
+            let rows = [
+                ("Name", info.name.clone()),
+                ("Backend", format!("{:?}", info.backend)),
+                ("Device Type", format!("{:?}", info.device_type)),
+                ("Driver", format!("{} {}", info.driver, info.driver_info)),
+            ];
+
+            for (name, value) in rows {
+                ui.label(name);
+                ui.label(value);
+                ui.end_row();
+            }
+        });
+
+        // every limit is listed through its debug output, so none are ever missed
+        let limits = format!("{:#?}", self.renderer.device.limits());
+
+        CollapsingHeader::new("Limits").show(ui, |ui| {
+            ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+                Grid::new("adapter_limits").striped(true).show(ui, |ui| {
+                    for (name, value) in limits.lines().filter_map(|line| line.split_once(':')) {
+                        ui.label(name.trim());
+                        ui.monospace(value.trim().trim_end_matches(','));
+                        ui.end_row();
+                    }
+                });
+            });
+        });
+    }
+
+    /// Writes a captured frame into the [`CAPTURE_DIRECTORY`], logging any failures.
+    fn save_capture(capture: &FrameCapture) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+
+        let path = format!("{CAPTURE_DIRECTORY}/capture-{timestamp}.png");
+
+        let result = std::fs::create_dir_all(CAPTURE_DIRECTORY)
+            .map_err(anyhow::Error::from)
+            .and_then(|_| capture.save_png(&path));
+
+        match result {
+            Ok(()) => log::info!("saved frame capture to {path}"),
+            Err(e) => log::error!("failed to save frame capture: {e:#}"),
+        }
+    }
+
+    /// Writes a dumped render graph into the [`CAPTURE_DIRECTORY`] as a Graphviz file, logging
+    /// any failures.
+    fn save_graph_dump(dot: &str) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+
+        let path = format!("{CAPTURE_DIRECTORY}/render-graph-{timestamp}.dot");
+
+        let result =
+            std::fs::create_dir_all(CAPTURE_DIRECTORY).and_then(|_| std::fs::write(&path, dot));
+
+        match result {
+            Ok(()) => log::info!("saved render graph to {path}"),
+            Err(e) => log::error!("failed to save render graph: {e}"),
+        }
+    }
+
+    /// Resizes the state of the app to match the new window size, pausing while the window has
+    /// a zero dimension.
     fn resize(&mut self, size: PhysicalSize<u32>) {
+        let paused = size.width == 0 || size.height == 0;
+
+        if self.paused && !paused {
+            // keys pressed while paused shouldn't take effect in the first frame after resuming
+            self.input.end_frame();
+            self.timer.resume();
+            self.window.request_redraw();
+        }
+
+        self.paused = paused;
+
         self.renderer.resize(size);
         self.camera.resize(size);
     }
@@ -166,6 +586,7 @@ pub struct AppHandler {
 
 impl AppHandler {
     /// Creates a new [`AppHandler`], the main entry point to the app.
+    #[allow(clippy::new_without_default)]
     pub fn new(#[cfg(target_arch = "wasm32")] proxy: EventLoopProxy<App>) -> Self {
         Self {
             #[cfg(target_arch = "wasm32")]
@@ -200,8 +621,12 @@ impl ApplicationHandler<App> for AppHandler {
         let window = Arc::new(event_loop.create_window(window_attributes).unwrap());
 
         #[cfg(not(target_arch = "wasm32"))]
-        {
-            self.app = Some(pollster::block_on(App::new(window)));
+        match pollster::block_on(App::new(window)) {
+            Ok(app) => self.app = Some(app),
+            Err(e) => {
+                log::error!("failed to start: {e:#}");
+                event_loop.exit();
+            }
         }
 
         #[cfg(target_arch = "wasm32")]
@@ -209,7 +634,10 @@ impl ApplicationHandler<App> for AppHandler {
             let proxy = self.proxy.take().unwrap();
 
             wasm_bindgen_futures::spawn_local(async move {
-                assert!(proxy.send_event(App::new(window).await).is_ok());
+                match App::new(window).await {
+                    Ok(app) => assert!(proxy.send_event(app).is_ok()),
+                    Err(e) => log::error!("failed to start: {e:#}"),
+                }
             });
         }
     }
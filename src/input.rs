@@ -10,6 +10,8 @@ use winit::{
 pub struct InputState {
     /// The keys currently being held down.
     pub keys_held: HashSet<KeyCode>,
+    /// The keys which were pressed down since the last frame (ignoring key repeats).
+    pub keys_pressed: HashSet<KeyCode>,
 
     /// The last known mouse position.
     pub last_mouse: Option<(f32, f32)>,
@@ -29,6 +31,7 @@ impl InputState {
     pub fn new(window: Arc<Window>) -> Self {
         Self {
             keys_held: HashSet::new(),
+            keys_pressed: HashSet::new(),
             last_mouse: None,
             mouse_delta: (0.0, 0.0),
             focused: false,
@@ -44,6 +47,7 @@ impl InputState {
                     KeyEvent {
                         physical_key: PhysicalKey::Code(code),
                         state,
+                        repeat,
                         ..
                     },
                 ..
@@ -57,6 +61,10 @@ impl InputState {
                     ElementState::Pressed => self.keys_held.insert(*code),
                     ElementState::Released => self.keys_held.remove(code),
                 };
+
+                if state.is_pressed() && !repeat {
+                    self.keys_pressed.insert(*code);
+                }
             }
 
             WindowEvent::MouseInput { .. } => {
@@ -76,6 +84,11 @@ impl InputState {
         }
     }
 
+    /// Clears all per-frame state, to be called once the frame has been processed.
+    pub fn end_frame(&mut self) {
+        self.keys_pressed.clear();
+    }
+
     /// Sets the state of focused, updating the cursor state as needed.
     pub fn set_focused(&mut self, focused: bool) {
         self.focused = focused;
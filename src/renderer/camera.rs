@@ -27,8 +27,10 @@ pub struct Camera {
 
 impl Camera {
     /// Returns the current view-projection transformation matrix.
+    ///
+    /// The projection maps depth in reverse, with the near plane at 1.0 and infinity at 0.0.
     pub fn view_projection(&self) -> Mat4 {
-        let projection = Mat4::perspective_infinite_rh(self.fov, self.aspect_ratio, 0.1);
+        let projection = Mat4::perspective_infinite_reverse_rh(self.fov, self.aspect_ratio, 0.1);
         let view = Mat4::look_to_rh(self.position, self.forward(), Vec3::Y);
 
         projection * view
@@ -43,6 +45,16 @@ impl Camera {
         )
     }
 
+    /// Returns the right vector, which stays horizontal as the camera has no roll.
+    pub fn right(&self) -> Vec3 {
+        Vec3::new(self.yaw.cos(), 0.0, self.yaw.sin())
+    }
+
+    /// Returns the up vector, perpendicular to both the forward and right vectors.
+    pub fn up(&self) -> Vec3 {
+        self.right().cross(self.forward())
+    }
+
     /// Updates the camera's position based on the user's input.
     pub fn update_position(&mut self, key_down: impl Fn(&KeyCode) -> bool, dt: f32) {
         let up = Vec3::Y;
@@ -85,9 +97,15 @@ impl Camera {
     }
 
     /// Resizes the camera's aspect ratio to match the new window size.
+    ///
+    /// Sizes with a zero dimension (such as minimized windows) keep the previous aspect ratio.
     pub fn resize(&mut self, size: PhysicalSize<u32>) {
         let PhysicalSize { width, height } = size;
 
+        if width == 0 || height == 0 {
+            return;
+        }
+
         self.aspect_ratio = width as f32 / height as f32;
     }
 }
@@ -0,0 +1,137 @@
+use std::{path::Path, sync::mpsc};
+
+use anyhow::{Context, bail};
+use wgpu::*;
+
+/// A frame read back from the GPU, stored as tightly packed RGBA8 pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameCapture {
+    /// The width of the frame (in pixels).
+    pub width: u32,
+    /// The height of the frame (in pixels).
+    pub height: u32,
+    /// The RGBA8 pixels of the frame, row by row without any padding.
+    pub pixels: Vec<u8>,
+}
+
+/// A copy of a frame that has been recorded, but not yet read back from the GPU.
+pub struct PendingCapture {
+    /// The buffer the frame is being copied into.
+    staging_buffer: Buffer,
+
+    /// The format of the copied texture.
+    format: TextureFormat,
+    /// The width of the copied texture (in pixels).
+    width: u32,
+    /// The height of the copied texture (in pixels).
+    height: u32,
+    /// The number of bytes per row in the `staging_buffer`, including the alignment padding.
+    padded_bytes_per_row: u32,
+}
+
+impl FrameCapture {
+    /// Writes the frame to a PNG file at the given path.
+    pub fn save_png(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+
+        image::save_buffer(
+            path,
+            &self.pixels,
+            self.width,
+            self.height,
+            image::ExtendedColorType::Rgba8,
+        )
+        .with_context(|| format!("failed to write capture to {}", path.display()))
+    }
+}
+
+impl PendingCapture {
+    /// Records a copy of the entire texture into a new staging buffer.
+    ///
+    /// The texture must have been created with [`TextureUsages::COPY_SRC`].
+    pub fn record(device: &Device, encoder: &mut CommandEncoder, texture: &Texture) -> Self {
+        let Extent3d { width, height, .. } = texture.size();
+        let format = texture.format();
+
+        let bytes_per_pixel = format.block_copy_size(None).unwrap_or(4);
+        let padded_bytes_per_row =
+            (width * bytes_per_pixel).next_multiple_of(COPY_BYTES_PER_ROW_ALIGNMENT);
+
+        let staging_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("PendingCapture::staging_buffer"),
+            size: padded_bytes_per_row as u64 * height as u64,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            TexelCopyBufferInfo {
+                buffer: &staging_buffer,
+                layout: TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            texture.size(),
+        );
+
+        Self {
+            staging_buffer,
+            format,
+            width,
+            height,
+            padded_bytes_per_row,
+        }
+    }
+
+    /// Waits for the copy to complete and converts the result to a [`FrameCapture`].
+    ///
+    /// The commands recording the copy must have already been submitted.
+    pub fn read(self, device: &Device) -> anyhow::Result<FrameCapture> {
+        let swizzle = match self.format {
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => false,
+            TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => true,
+            format => bail!("cannot capture frames with format {format:?}"),
+        };
+
+        let slice = self.staging_buffer.slice(..);
+        let (sender, receiver) = mpsc::channel();
+
+        slice.map_async(MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+
+        device.poll(PollType::wait_indefinitely())?;
+
+        receiver
+            .try_recv()
+            .context("the staging buffer was not mapped in time")??;
+
+        let row_size = (self.width * 4) as usize;
+        let mut pixels = Vec::with_capacity(row_size * self.height as usize);
+
+        {
+            let data = slice.get_mapped_range();
+
+            for row in data.chunks_exact(self.padded_bytes_per_row as usize) {
+                pixels.extend_from_slice(&row[..row_size]);
+            }
+        }
+
+        self.staging_buffer.unmap();
+
+        if swizzle {
+            for pixel in pixels.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+
+        Ok(FrameCapture {
+            width: self.width,
+            height: self.height,
+            pixels,
+        })
+    }
+}
@@ -0,0 +1,262 @@
+use std::sync::mpsc;
+
+use anyhow::{Context, anyhow, bail};
+use bytemuck::Pod;
+use naga::valid::{Capabilities, ValidationFlags, Validator};
+use wgpu::*;
+
+use crate::renderer::{pipelines::Pipelines, shaders::reflection::ShaderReflection};
+
+/// A compute pipeline, along with the bind group layouts reflected from its shader.
+#[derive(Clone)]
+pub struct ComputeShader {
+    /// The pipeline running the entry point of the shader.
+    pub pipeline: ComputePipeline,
+    /// The layout of every bind group declared by the shader, by group index.
+    pub bind_group_layouts: Vec<BindGroupLayout>,
+    /// The binding numbers within every bind group, in increasing order.
+    bindings: Vec<Vec<u32>>,
+    /// The number of invocations within a single workgroup, along each dimension.
+    pub workgroup_size: [u32; 3],
+}
+
+/// A dispatch of a [`ComputeShader`], recorded into a compute pass.
+#[derive(Clone)]
+pub struct ComputeDispatch {
+    /// The shader being dispatched.
+    pub shader: ComputeShader,
+    /// The bind groups set before dispatching, by group index.
+    pub bind_groups: Vec<BindGroup>,
+    /// The number of workgroups dispatched along each dimension.
+    pub workgroups: [u32; 3],
+}
+
+impl ComputeShader {
+    /// Validates and compiles the given WGSL source, creating a pipeline running the given entry
+    /// point with bind group layouts reflected from the source.
+    ///
+    /// Fails if the source is invalid (including using features the device lacks), or doesn't
+    /// have a compute entry point with that name.
+    pub fn new(
+        device: &Device,
+        label: &str,
+        source: &str,
+        entry_point: &str,
+    ) -> anyhow::Result<Self> {
+        let module = naga::front::wgsl::parse_str(source)
+            .map_err(|e| anyhow!("{}", e.emit_to_string(source)))
+            .with_context(|| format!("failed to parse compute shader {label}"))?;
+
+        let info = Validator::new(ValidationFlags::all(), Capabilities::all())
+            .validate(&module)
+            .map_err(|e| anyhow!("{}", e.emit_to_string(source)))
+            .with_context(|| format!("failed to validate compute shader {label}"))?;
+
+        let reflection = ShaderReflection::new(&module, &info)
+            .map_err(|e| anyhow!("failed to reflect compute shader {label}: {e}"))?;
+
+        // naga validation allows every capability, leaving the device's features to wgpu
+        device.push_error_scope(ErrorFilter::Validation);
+
+        let module = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some(label),
+            source: ShaderSource::Wgsl(source.into()),
+        });
+
+        let shader = Self::from_module(device, label, (&module, entry_point), &reflection);
+
+        let error = pollster::block_on(device.pop_error_scope());
+
+        let shader = shader?;
+
+        if let Some(error) = error {
+            bail!("failed to compile compute shader {label}: {error}");
+        }
+
+        Ok(shader)
+    }
+
+    /// Creates a pipeline running the given entry point of an already compiled module, with bind
+    /// group layouts created from its reflection.
+    ///
+    /// Fails if the module doesn't have a compute entry point with that name.
+    pub fn from_module(
+        device: &Device,
+        label: &str,
+        (module, entry_point): (&ShaderModule, &str),
+        reflection: &ShaderReflection,
+    ) -> anyhow::Result<Self> {
+        let workgroup_size = reflection
+            .workgroup_size(entry_point)
+            .with_context(|| format!("{label} has no compute entry point named {entry_point}"))?;
+
+        let bind_group_layouts = (0..reflection.bind_groups.len())
+            .map(|group| {
+                Pipelines::create_bind_group_layout(
+                    device,
+                    &format!("{label}_bind_group_layout({group})"),
+                    reflection,
+                    group,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some(&format!("{label}_layout")),
+            bind_group_layouts: &bind_group_layouts.iter().collect::<Vec<_>>(),
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some(label),
+            layout: Some(&layout),
+            module,
+            entry_point: Some(entry_point),
+            compilation_options: PipelineCompilationOptions::default(),
+            cache: None,
+        });
+
+        let bindings = reflection
+            .bind_groups
+            .iter()
+            .map(|entries| entries.iter().map(|entry| entry.binding).collect())
+            .collect();
+
+        Ok(Self {
+            pipeline,
+            bind_group_layouts,
+            bindings,
+            workgroup_size,
+        })
+    }
+
+    /// Creates one of the bind groups declared by the shader, binding the given resources (such
+    /// as storage buffers and storage textures) in the order of their binding numbers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the group isn't declared by the shader, or the number of resources doesn't match
+    /// the number of bindings in the group.
+    pub fn create_bind_group(
+        &self,
+        device: &Device,
+        group: usize,
+        resources: &[BindingResource],
+    ) -> BindGroup {
+        let bindings = &self.bindings[group];
+
+        assert_eq!(
+            bindings.len(),
+            resources.len(),
+            "bind group {group} has {} bindings, but {} resources were given",
+            bindings.len(),
+            resources.len()
+        );
+
+        let entries = bindings
+            .iter()
+            .zip(resources)
+            .map(|(&binding, resource)| BindGroupEntry {
+                binding,
+                resource: resource.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some(&format!("ComputeShader::bind_group({group})")),
+            layout: &self.bind_group_layouts[group],
+            entries: &entries,
+        })
+    }
+
+    /// Returns the number of workgroups needed for the shader to be invoked at least once for
+    /// every one of the given invocations along each dimension.
+    pub fn workgroups_for(&self, invocations: [u32; 3]) -> [u32; 3] {
+        [0, 1, 2].map(|i| invocations[i].div_ceil(self.workgroup_size[i]))
+    }
+}
+
+impl ComputeDispatch {
+    /// Creates a dispatch invoking the shader at least once for every one of the given
+    /// invocations along each dimension, with the given bind groups.
+    pub fn new(shader: &ComputeShader, bind_groups: Vec<BindGroup>, invocations: [u32; 3]) -> Self {
+        Self {
+            shader: shader.clone(),
+            bind_groups,
+            workgroups: shader.workgroups_for(invocations),
+        }
+    }
+
+    /// Records the dispatch into the given pass.
+    pub fn record(&self, pass: &mut ComputePass) {
+        let [x, y, z] = self.workgroups;
+
+        pass.set_pipeline(&self.shader.pipeline);
+
+        for (group, bind_group) in self.bind_groups.iter().enumerate() {
+            pass.set_bind_group(group as u32, bind_group, &[]);
+        }
+
+        pass.dispatch_workgroups(x, y, z);
+    }
+}
+
+/// Copies the contents of a buffer back from the GPU, waiting for every previously submitted
+/// command to complete (such as compute dispatches writing into it).
+///
+/// The buffer must have been created with [`BufferUsages::COPY_SRC`], and its size must be a
+/// multiple of both the size of `T` and [`COPY_BUFFER_ALIGNMENT`] (as buffers are copied in 4 byte
+/// words), failing otherwise.
+pub fn read_buffer<T: Pod>(
+    device: &Device,
+    queue: &Queue,
+    buffer: &Buffer,
+) -> anyhow::Result<Vec<T>> {
+    let size = buffer.size();
+
+    if !size.is_multiple_of(COPY_BUFFER_ALIGNMENT) {
+        bail!("a buffer of {size} bytes can't be copied, as copies are made of 4 byte words");
+    }
+
+    if !size.is_multiple_of(size_of::<T>() as u64) {
+        bail!(
+            "a buffer of {size} bytes can't hold elements of {} bytes",
+            size_of::<T>()
+        );
+    }
+
+    // empty buffers can't be mapped
+    if size == 0 {
+        return Ok(Vec::new());
+    }
+
+    let staging_buffer = device.create_buffer(&BufferDescriptor {
+        label: Some("read_buffer::staging_buffer"),
+        size,
+        usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor::default());
+    encoder.copy_buffer_to_buffer(buffer, 0, &staging_buffer, 0, size);
+    queue.submit([encoder.finish()]);
+
+    let slice = staging_buffer.slice(..);
+    let (sender, receiver) = mpsc::channel();
+
+    slice.map_async(MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+
+    device.poll(PollType::wait_indefinitely())?;
+
+    receiver
+        .try_recv()
+        .context("the staging buffer was not mapped in time")??;
+
+    let contents = bytemuck::pod_collect_to_vec(&slice.get_mapped_range());
+
+    staging_buffer.unmap();
+
+    Ok(contents)
+}
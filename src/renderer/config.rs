@@ -0,0 +1,397 @@
+use std::fmt;
+
+use anyhow::{Context, bail};
+use wgpu::*;
+
+use crate::renderer::pipelines::TargetFormats;
+
+/// Controls how a [`Renderer`](crate::renderer::Renderer) chooses its adapter and sets up its
+/// device and target.
+#[derive(Debug, Clone)]
+pub struct RendererConfig {
+    /// The graphics APIs which may be used.
+    pub backends: Backends,
+    /// Whether a faster or a more power efficient adapter is preferred.
+    pub power_preference: PowerPreference,
+    /// The adapter to render with, instead of letting the backend choose one.
+    pub adapter: Option<AdapterSelection>,
+    /// Whether a software adapter is required, which is useful on machines without a GPU (such as
+    /// CI runners).
+    pub force_fallback_adapter: bool,
+    /// The features the device must support.
+    pub required_features: Features,
+    /// The limits the device must support.
+    pub required_limits: Limits,
+    /// The number of samples taken per pixel by the main render pass.
+    pub sample_count: u32,
+    /// How frames are presented to a window.
+    pub present_mode: PresentMode,
+    /// The format of the window's surface, if supported (otherwise an sRGB format is chosen).
+    pub surface_format: Option<TextureFormat>,
+}
+
+/// Picks one of the adapters listed by [`RendererConfig::available_adapters`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AdapterSelection {
+    /// The adapter at the given position in the list.
+    Index(usize),
+    /// The first adapter whose name contains the given text (ignoring case).
+    Name(String),
+}
+
+/// A reason why a [`RendererConfig`] can't be used with an adapter or target.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    /// The adapter lacks some of the required features.
+    MissingFeatures(Features),
+    /// Some of the required limits are beyond what the adapter supports.
+    ExceededLimits(Vec<ExceededLimit>),
+    /// The sample count is not supported for one of the target's formats.
+    UnsupportedSampleCount {
+        /// The requested sample count.
+        sample_count: u32,
+        /// The format not supporting the sample count.
+        format: TextureFormat,
+        /// Every sample count supported by the format.
+        supported: Vec<u32>,
+    },
+    /// The present mode is not supported by the target.
+    UnsupportedPresentMode {
+        /// The requested present mode.
+        present_mode: PresentMode,
+        /// Every present mode supported by the target.
+        supported: Vec<PresentMode>,
+    },
+}
+
+/// A single required limit which the adapter doesn't support.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExceededLimit {
+    /// The name of the limit.
+    pub name: &'static str,
+    /// The value which was required.
+    pub required: u64,
+    /// The best value supported by the adapter.
+    pub allowed: u64,
+}
+
+impl Default for RendererConfig {
+    fn default() -> Self {
+        Self {
+            backends: Backends::PRIMARY,
+            power_preference: PowerPreference::HighPerformance,
+            adapter: None,
+            force_fallback_adapter: false,
+            required_features: Features::empty(),
+            required_limits: Limits::default(),
+            sample_count: 1,
+            present_mode: PresentMode::AutoVsync,
+            surface_format: None,
+        }
+    }
+}
+
+impl AdapterSelection {
+    /// The environment variable an adapter can be selected with, holding either its index or
+    /// (part of) its name.
+    pub const ENV_VAR: &str = "GPU_TEMPLATE_ADAPTER";
+
+    /// Parses a selection, which is an index if the text is a number and a name otherwise.
+    pub fn parse(text: &str) -> Self {
+        let text = text.trim();
+
+        match text.parse() {
+            Ok(index) => Self::Index(index),
+            Err(_) => Self::Name(text.to_owned()),
+        }
+    }
+
+    /// Returns the selection within the [`AdapterSelection::ENV_VAR`] variable, if it is set.
+    pub fn from_env() -> Option<Self> {
+        std::env::var(Self::ENV_VAR)
+            .ok()
+            .filter(|text| !text.trim().is_empty())
+            .map(|text| Self::parse(&text))
+    }
+
+    /// Returns whether the adapter at the given index (with the given info) is selected.
+    pub fn matches(&self, index: usize, info: &AdapterInfo) -> bool {
+        match self {
+            Self::Index(selected) => index == *selected,
+            Self::Name(name) => info.name.to_lowercase().contains(&name.to_lowercase()),
+        }
+    }
+}
+
+impl fmt::Display for AdapterSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Index(index) => write!(f, "adapter #{index}"),
+            Self::Name(name) => write!(f, "adapter named \"{name}\""),
+        }
+    }
+}
+
+impl RendererConfig {
+    /// Returns the default configuration, with the adapter selected by the
+    /// [`AdapterSelection::ENV_VAR`] environment variable (if it is set).
+    pub fn from_env() -> Self {
+        Self {
+            adapter: AdapterSelection::from_env(),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the default configuration for a headless renderer, which allows every backend so
+    /// that software adapters (which may only be available through GL) are found.
+    pub fn headless() -> Self {
+        Self {
+            backends: Backends::all(),
+            ..Default::default()
+        }
+    }
+
+    /// Clears the adapter selection if it matches none of the given adapters (as listed by
+    /// [`RendererConfig::available_adapters`]), so that the default adapter is used instead.
+    ///
+    /// Returns the cleared selection, if it was cleared.
+    pub fn clear_unavailable_adapter(
+        &mut self,
+        adapters: &[AdapterInfo],
+    ) -> Option<AdapterSelection> {
+        let selection = self.adapter.as_ref()?;
+
+        if adapters
+            .iter()
+            .enumerate()
+            .any(|(index, info)| selection.matches(index, info))
+        {
+            return None;
+        }
+
+        self.adapter.take()
+    }
+
+    /// Returns the info of every adapter available with the configured backends, in the order
+    /// used by [`AdapterSelection::Index`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn available_adapters(&self) -> Vec<AdapterInfo> {
+        Instance::new(&self.instance_descriptor())
+            .enumerate_adapters(self.backends)
+            .iter()
+            .map(Adapter::get_info)
+            .collect()
+    }
+
+    /// Requests the adapter to render with, optionally compatible with a surface.
+    ///
+    /// If an adapter is selected, it is used regardless of the power preference and fallback
+    /// options (adapters can't be listed on the web, where the selection is ignored).
+    pub async fn request_adapter(
+        &self,
+        instance: &Instance,
+        compatible_surface: Option<&Surface<'_>>,
+    ) -> anyhow::Result<Adapter> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(selection) = &self.adapter {
+            return Self::select_adapter(instance, self.backends, selection, compatible_surface);
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        if let Some(selection) = &self.adapter {
+            log::warn!("ignoring the selected {selection}, adapters can't be listed on the web");
+        }
+
+        instance
+            .request_adapter(&self.adapter_options(compatible_surface))
+            .await
+            .context("failed to find an adapter")
+    }
+
+    /// Finds the selected adapter among those available, failing if it isn't available or can't
+    /// present to the surface.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn select_adapter(
+        instance: &Instance,
+        backends: Backends,
+        selection: &AdapterSelection,
+        compatible_surface: Option<&Surface<'_>>,
+    ) -> anyhow::Result<Adapter> {
+        let adapters = instance.enumerate_adapters(backends);
+
+        let compatible = |adapter: &Adapter| {
+            compatible_surface.is_none_or(|surface| adapter.is_surface_supported(surface))
+        };
+
+        let selected = adapters
+            .iter()
+            .enumerate()
+            .filter(|(index, adapter)| selection.matches(*index, &adapter.get_info()))
+            .map(|(_, adapter)| adapter)
+            .collect::<Vec<_>>();
+
+        if let Some(adapter) = selected.iter().find(|adapter| compatible(adapter)) {
+            return Ok((*adapter).clone());
+        }
+
+        match selected.first() {
+            Some(adapter) => bail!(
+                "the selected adapter {} can't present to the window",
+                adapter.get_info().name
+            ),
+            None => {
+                let available = adapters
+                    .iter()
+                    .enumerate()
+                    .map(|(index, adapter)| format!("#{index} {}", adapter.get_info().name))
+                    .collect::<Vec<_>>();
+
+                bail!(
+                    "no {selection} is available (available: {})",
+                    available.join(", ")
+                )
+            }
+        }
+    }
+
+    /// Returns the descriptor of the instance adapters are chosen from.
+    pub fn instance_descriptor(&self) -> InstanceDescriptor {
+        InstanceDescriptor {
+            backends: self.backends,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the options used to request an adapter, optionally compatible with a surface.
+    pub fn adapter_options<'a>(
+        &self,
+        compatible_surface: Option<&'a Surface<'a>>,
+    ) -> RequestAdapterOptions<'a, 'a> {
+        RequestAdapterOptions {
+            power_preference: self.power_preference,
+            force_fallback_adapter: self.force_fallback_adapter,
+            compatible_surface,
+        }
+    }
+
+    /// Returns the descriptor used to request the device.
+    pub fn device_descriptor(&self) -> DeviceDescriptor<'static> {
+        DeviceDescriptor {
+            label: Some("Renderer::device"),
+            required_features: self.features(),
+            required_limits: self.required_limits.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Returns every feature the device needs, including those needed by the sample count.
+    pub fn features(&self) -> Features {
+        match self.sample_count {
+            // the only sample counts guaranteed by WebGPU, others depend on the adapter
+            1 | 4 => self.required_features,
+            _ => self.required_features | Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES,
+        }
+    }
+
+    /// Checks that the adapter supports every required feature and limit.
+    pub fn check_adapter(&self, adapter: &Adapter) -> Result<(), ConfigError> {
+        let missing = self.features() - adapter.features();
+
+        if !missing.is_empty() {
+            return Err(ConfigError::MissingFeatures(missing));
+        }
+
+        let mut exceeded = Vec::new();
+
+        self.required_limits.check_limits_with_fail_fn(
+            &adapter.limits(),
+            false,
+            |name, required, allowed| {
+                exceeded.push(ExceededLimit {
+                    name,
+                    required,
+                    allowed,
+                })
+            },
+        );
+
+        match exceeded.is_empty() {
+            true => Ok(()),
+            false => Err(ConfigError::ExceededLimits(exceeded)),
+        }
+    }
+
+    /// Checks that a target with the given formats and present modes can be rendered into by the
+    /// adapter.
+    pub fn check_target(
+        &self,
+        adapter: &Adapter,
+        formats: TargetFormats,
+        present_modes: &[PresentMode],
+    ) -> Result<(), ConfigError> {
+        for format in [formats.color, formats.depth] {
+            let flags = adapter.get_texture_format_features(format).flags;
+
+            if !flags.sample_count_supported(self.sample_count) {
+                return Err(ConfigError::UnsupportedSampleCount {
+                    sample_count: self.sample_count,
+                    format,
+                    supported: flags.supported_sample_counts(),
+                });
+            }
+        }
+
+        if !present_modes.contains(&self.present_mode) {
+            return Err(ConfigError::UnsupportedPresentMode {
+                present_mode: self.present_mode,
+                supported: present_modes.to_vec(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingFeatures(features) => {
+                write!(f, "the adapter does not support the features {features:?}")
+            }
+            Self::ExceededLimits(limits) => {
+                let limits = limits
+                    .iter()
+                    .map(|limit| {
+                        format!(
+                            "{} (required {}, allowed {})",
+                            limit.name, limit.required, limit.allowed
+                        )
+                    })
+                    .collect::<Vec<_>>();
+
+                write!(
+                    f,
+                    "the adapter does not support the limits {}",
+                    limits.join(", ")
+                )
+            }
+            Self::UnsupportedSampleCount {
+                sample_count,
+                format,
+                supported,
+            } => write!(
+                f,
+                "{format:?} does not support {sample_count} samples (supported: {supported:?})"
+            ),
+            Self::UnsupportedPresentMode {
+                present_mode,
+                supported,
+            } => write!(
+                f,
+                "the target does not support {present_mode:?} (supported: {supported:?})"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
@@ -0,0 +1,87 @@
+use wgpu::*;
+
+/// A depth texture matching the size of the render target.
+///
+/// Depth is stored reversed (1.0 at the near plane, 0.0 at infinity) to pair with the infinite
+/// projection of the camera, which keeps precision spread evenly across distances.
+pub struct DepthTexture {
+    /// The underlying depth texture.
+    pub texture: Texture,
+    /// A view into the whole `texture`.
+    pub view: TextureView,
+}
+
+impl DepthTexture {
+    /// The depth format used when none is configured.
+    pub const DEFAULT_FORMAT: TextureFormat = TextureFormat::Depth32Float;
+
+    /// The value the depth texture is cleared to at the start of a frame (the far plane).
+    pub const CLEAR_VALUE: f32 = 0.0;
+
+    /// Creates a new [`DepthTexture`] of the given size, format and sample count.
+    pub fn new(
+        device: &Device,
+        format: TextureFormat,
+        width: u32,
+        height: u32,
+        sample_count: u32,
+    ) -> Self {
+        // multisampled depth textures can't be attached alongside multisampled color renderbuffers
+        // on some backends (such as GL), so they are only ever used as attachments
+        let usage = match sample_count {
+            1 => TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            _ => TextureUsages::RENDER_ATTACHMENT,
+        };
+
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("DepthTexture::texture"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: TextureDimension::D2,
+            format,
+            usage,
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&TextureViewDescriptor::default());
+
+        Self { texture, view }
+    }
+
+    /// Returns the format of the depth texture.
+    pub fn format(&self) -> TextureFormat {
+        self.texture.format()
+    }
+
+    /// Returns the depth state for pipelines which test and write against a depth texture of the
+    /// given format.
+    pub fn depth_stencil_state(format: TextureFormat) -> DepthStencilState {
+        DepthStencilState {
+            format,
+            depth_write_enabled: true,
+            depth_compare: CompareFunction::GreaterEqual,
+            stencil: StencilState::default(),
+            bias: DepthBiasState::default(),
+        }
+    }
+
+    /// Returns the attachment clearing and storing the depth texture in a render pass.
+    pub fn attachment(&self) -> RenderPassDepthStencilAttachment<'_> {
+        RenderPassDepthStencilAttachment {
+            view: &self.view,
+            depth_ops: Some(Operations {
+                load: LoadOp::Clear(Self::CLEAR_VALUE),
+                store: StoreOp::Store,
+            }),
+            stencil_ops: self.format().has_stencil_aspect().then_some(Operations {
+                load: LoadOp::Clear(0),
+                store: StoreOp::Store,
+            }),
+        }
+    }
+}
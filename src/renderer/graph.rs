@@ -0,0 +1,668 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+    fmt::{self, Write},
+};
+
+use wgpu::*;
+
+/// Identifies a resource (texture or buffer) within a [`RenderGraph`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ResourceId(usize);
+
+/// Identifies a texture within a [`RenderGraph`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextureHandle(ResourceId);
+
+/// Identifies a buffer within a [`RenderGraph`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BufferHandle(ResourceId);
+
+/// Describes a texture allocated by the graph, which only lives for the passes using it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TransientTexture {
+    /// The width of the texture (in pixels).
+    pub width: u32,
+    /// The height of the texture (in pixels).
+    pub height: u32,
+    /// The format of the texture.
+    pub format: TextureFormat,
+    /// How the texture is used by the passes.
+    pub usage: TextureUsages,
+}
+
+/// Describes a buffer allocated by the graph, which only lives for the passes using it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TransientBuffer {
+    /// The size of the buffer (in bytes).
+    pub size: u64,
+    /// How the buffer is used by the passes.
+    pub usage: BufferUsages,
+}
+
+/// A reason why a [`RenderGraph`] can't be executed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GraphError {
+    /// A pass reads a transient resource before any pass has written it.
+    UninitializedRead {
+        /// The name of the reading pass.
+        pass: String,
+        /// The name of the resource.
+        resource: String,
+    },
+}
+
+/// The passes making up a frame, along with the textures and buffers they read and write.
+///
+/// Every resource is accessed in the order the passes using it were added: a pass reading a
+/// resource runs after the previous pass writing it, and a pass writing a resource runs after
+/// every previous pass reading or writing it. Passes are then ordered topologically (keeping the
+/// order they were added in between independent passes), and passes which contribute nothing to
+/// an imported resource are culled.
+///
+/// Transient resources are allocated from a [`TransientPool`], with resources of the same
+/// description sharing memory whenever their lifetimes don't overlap.
+#[derive(Default)]
+pub struct RenderGraph<'a> {
+    /// Every resource declared so far, by [`ResourceId`].
+    resources: Vec<Resource<'a>>,
+    /// Every pass added so far, in the order they were added.
+    passes: Vec<Pass<'a>>,
+}
+
+/// A resource declared in a [`RenderGraph`].
+struct Resource<'a> {
+    /// The name of the resource, used for debugging.
+    name: String,
+    /// Where the resource comes from.
+    source: ResourceSource<'a>,
+}
+
+/// Where a resource used by a [`RenderGraph`] comes from.
+enum ResourceSource<'a> {
+    /// A texture owned outside the graph.
+    ImportedTexture(&'a TextureView),
+    /// A buffer owned outside the graph.
+    ImportedBuffer(&'a Buffer),
+    /// A resource allocated by the graph.
+    Transient(TransientResource),
+}
+
+/// Describes a resource allocated by the graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum TransientResource {
+    /// See [`TransientTexture`].
+    Texture(TransientTexture),
+    /// See [`TransientBuffer`].
+    Buffer(TransientBuffer),
+}
+
+/// A pass added to a [`RenderGraph`].
+struct Pass<'a> {
+    /// The name of the pass, used for debugging and labelling.
+    name: String,
+    /// The accesses declared by the pass.
+    accesses: PassBuilder,
+    /// Records the commands of the pass.
+    execute: Box<dyn FnOnce(&mut PassContext) + 'a>,
+}
+
+/// Declares the resources a pass reads and writes.
+#[derive(Debug, Default)]
+pub struct PassBuilder {
+    /// Every resource read by the pass.
+    reads: Vec<ResourceId>,
+    /// Every resource written by the pass.
+    writes: Vec<ResourceId>,
+    /// Whether the pass affects something outside the graph, so that it is never culled.
+    side_effects: bool,
+}
+
+/// The state available to a pass while recording its commands.
+pub struct PassContext<'p> {
+    /// The device the graph is executed on.
+    pub device: &'p Device,
+    /// The encoder the commands of the pass are recorded into.
+    pub encoder: &'p mut CommandEncoder,
+    /// The physical texture or buffer of every resource used by a scheduled pass.
+    resources: &'p HashMap<ResourceId, PhysicalResource<'p>>,
+}
+
+/// The actual texture or buffer backing a resource.
+#[derive(Clone, Copy)]
+enum PhysicalResource<'p> {
+    /// A view into the whole texture.
+    Texture(&'p TextureView),
+    /// The buffer.
+    Buffer(&'p Buffer),
+}
+
+/// The result of compiling a [`RenderGraph`]: the order passes run in, and where every transient
+/// resource is allocated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    /// The indices of the passes which run, in the order they run.
+    order: Vec<usize>,
+    /// The names of the passes which run, in the order they run.
+    pub passes: Vec<String>,
+    /// The names of the passes which were culled.
+    pub culled: Vec<String>,
+    /// The description and slot within the [`TransientPool`] of every transient resource used by
+    /// a scheduled pass.
+    slots: HashMap<ResourceId, (TransientResource, usize)>,
+}
+
+/// Keeps the textures and buffers allocated for transient resources across frames, so that they
+/// are only created again when their descriptions change.
+#[derive(Default)]
+pub struct TransientPool {
+    /// Every texture allocated so far, along with a view into it, by description and slot.
+    textures: HashMap<TransientTexture, Vec<(Texture, TextureView)>>,
+    /// Every buffer allocated so far, by description and slot.
+    buffers: HashMap<TransientBuffer, Vec<Buffer>>,
+}
+
+impl From<TextureHandle> for ResourceId {
+    fn from(handle: TextureHandle) -> Self {
+        handle.0
+    }
+}
+
+impl From<BufferHandle> for ResourceId {
+    fn from(handle: BufferHandle) -> Self {
+        handle.0
+    }
+}
+
+impl<'a> RenderGraph<'a> {
+    /// Creates an empty [`RenderGraph`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares a texture owned outside the graph (such as the frame being rendered onto).
+    pub fn import_texture(&mut self, name: &str, view: &'a TextureView) -> TextureHandle {
+        TextureHandle(self.add_resource(name, ResourceSource::ImportedTexture(view)))
+    }
+
+    /// Declares a buffer owned outside the graph.
+    pub fn import_buffer(&mut self, name: &str, buffer: &'a Buffer) -> BufferHandle {
+        BufferHandle(self.add_resource(name, ResourceSource::ImportedBuffer(buffer)))
+    }
+
+    /// Declares a texture allocated by the graph, which must be written before being read.
+    pub fn create_texture(&mut self, name: &str, texture: TransientTexture) -> TextureHandle {
+        let source = ResourceSource::Transient(TransientResource::Texture(texture));
+        TextureHandle(self.add_resource(name, source))
+    }
+
+    /// Declares a buffer allocated by the graph, which must be written before being read.
+    pub fn create_buffer(&mut self, name: &str, buffer: TransientBuffer) -> BufferHandle {
+        let source = ResourceSource::Transient(TransientResource::Buffer(buffer));
+        BufferHandle(self.add_resource(name, source))
+    }
+
+    /// Declares a new resource, returning its id.
+    fn add_resource(&mut self, name: &str, source: ResourceSource<'a>) -> ResourceId {
+        self.resources.push(Resource {
+            name: name.to_owned(),
+            source,
+        });
+
+        ResourceId(self.resources.len() - 1)
+    }
+
+    /// Adds a pass to the graph, whose setup declares the resources it accesses and returns the
+    /// function recording its commands.
+    pub fn add_pass<E>(&mut self, name: &str, setup: impl FnOnce(&mut PassBuilder) -> E)
+    where
+        E: FnOnce(&mut PassContext) + 'a,
+    {
+        let mut accesses = PassBuilder::default();
+        let execute = setup(&mut accesses);
+
+        self.passes.push(Pass {
+            name: name.to_owned(),
+            accesses,
+            execute: Box::new(execute),
+        });
+    }
+
+    /// Orders the passes, culls the ones which aren't needed and assigns every transient resource
+    /// to a slot of the pool.
+    pub fn compile(&self) -> Result<Schedule, GraphError> {
+        let dependencies = self.dependencies()?;
+        let live = self.live_passes(&dependencies);
+
+        // Kahn's algorithm, always picking the earliest added pass among the ready ones
+        let mut remaining = vec![0; self.passes.len()];
+        let mut dependents = vec![Vec::new(); self.passes.len()];
+
+        for (pass, pass_dependencies) in dependencies.iter().enumerate() {
+            for &dependency in pass_dependencies {
+                remaining[pass] += 1;
+                dependents[dependency].push(pass);
+            }
+        }
+
+        let mut ready = (0..self.passes.len())
+            .filter(|&pass| remaining[pass] == 0)
+            .map(Reverse)
+            .collect::<BinaryHeap<_>>();
+
+        let mut order = Vec::with_capacity(self.passes.len());
+
+        while let Some(Reverse(pass)) = ready.pop() {
+            if live.contains(&pass) {
+                order.push(pass);
+            }
+
+            for &dependent in &dependents[pass] {
+                remaining[dependent] -= 1;
+
+                if remaining[dependent] == 0 {
+                    ready.push(Reverse(dependent));
+                }
+            }
+        }
+
+        let culled = (0..self.passes.len())
+            .filter(|pass| !live.contains(pass))
+            .map(|pass| self.passes[pass].name.clone())
+            .collect();
+
+        Ok(Schedule {
+            passes: order
+                .iter()
+                .map(|&pass| self.passes[pass].name.clone())
+                .collect(),
+            culled,
+            slots: self.assign_slots(&order),
+            order,
+        })
+    }
+
+    /// Returns the passes every pass depends on, derived from the order resources are accessed
+    /// in.
+    fn dependencies(&self) -> Result<Vec<HashSet<usize>>, GraphError> {
+        let mut dependencies = vec![HashSet::new(); self.passes.len()];
+
+        let mut last_writer = HashMap::<ResourceId, usize>::new();
+        let mut readers = HashMap::<ResourceId, Vec<usize>>::new();
+
+        for (index, pass) in self.passes.iter().enumerate() {
+            for &resource in &pass.accesses.reads {
+                match last_writer.get(&resource) {
+                    Some(&writer) => {
+                        dependencies[index].insert(writer);
+                    }
+                    None if self.is_transient(resource) => {
+                        return Err(GraphError::UninitializedRead {
+                            pass: pass.name.clone(),
+                            resource: self.resources[resource.0].name.clone(),
+                        });
+                    }
+                    None => {}
+                }
+
+                readers.entry(resource).or_default().push(index);
+            }
+
+            for &resource in &pass.accesses.writes {
+                dependencies[index].extend(last_writer.insert(resource, index));
+                dependencies[index].extend(readers.remove(&resource).unwrap_or_default());
+            }
+
+            dependencies[index].remove(&index);
+        }
+
+        Ok(dependencies)
+    }
+
+    /// Returns every pass which has side effects, writes an imported resource or writes
+    /// something read by such a pass.
+    fn live_passes(&self, dependencies: &[HashSet<usize>]) -> HashSet<usize> {
+        let mut live = HashSet::new();
+
+        let mut stack = self
+            .passes
+            .iter()
+            .enumerate()
+            .filter(|(_, pass)| {
+                pass.accesses.side_effects
+                    || pass
+                        .accesses
+                        .writes
+                        .iter()
+                        .any(|&resource| !self.is_transient(resource))
+            })
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+
+        while let Some(pass) = stack.pop() {
+            if !live.insert(pass) {
+                continue;
+            }
+
+            // passes which only read what this pass later overwrites aren't needed by it
+            stack.extend(dependencies[pass].iter().copied().filter(|&dependency| {
+                self.passes[dependency]
+                    .accesses
+                    .writes
+                    .iter()
+                    .any(|resource| self.passes[pass].accesses.accesses(*resource))
+            }));
+        }
+
+        live
+    }
+
+    /// Assigns every transient resource used by the scheduled passes to a slot of the pool, with
+    /// resources of the same description sharing a slot whenever their lifetimes don't overlap.
+    fn assign_slots(&self, order: &[usize]) -> HashMap<ResourceId, (TransientResource, usize)> {
+        // the first and last position in the order at which every transient resource is used
+        let mut lifetimes = HashMap::<ResourceId, (usize, usize)>::new();
+
+        for (position, &pass) in order.iter().enumerate() {
+            let accesses = &self.passes[pass].accesses;
+
+            for &resource in accesses.reads.iter().chain(&accesses.writes) {
+                if self.is_transient(resource) {
+                    lifetimes
+                        .entry(resource)
+                        .and_modify(|(_, last)| *last = position)
+                        .or_insert((position, position));
+                }
+            }
+        }
+
+        let mut resources = lifetimes.into_iter().collect::<Vec<_>>();
+        resources.sort_by_key(|&(resource, (first, _))| (first, resource));
+
+        // the position of the last use of every slot, by description
+        let mut slot_ends = HashMap::<TransientResource, Vec<usize>>::new();
+        let mut slots = HashMap::new();
+
+        for (resource, (first, last)) in resources {
+            let ResourceSource::Transient(description) = self.resources[resource.0].source else {
+                unreachable!("only transient resources have lifetimes");
+            };
+
+            let ends = slot_ends.entry(description).or_default();
+
+            let slot = match ends.iter().position(|&end| end < first) {
+                Some(slot) => {
+                    ends[slot] = last;
+                    slot
+                }
+                None => {
+                    ends.push(last);
+                    ends.len() - 1
+                }
+            };
+
+            slots.insert(resource, (description, slot));
+        }
+
+        slots
+    }
+
+    /// Returns whether the resource is allocated by the graph.
+    fn is_transient(&self, resource: ResourceId) -> bool {
+        matches!(
+            self.resources[resource.0].source,
+            ResourceSource::Transient(_)
+        )
+    }
+
+    /// Runs every scheduled pass in order, recording their commands into the encoder and
+    /// allocating transient resources from the pool.
+    pub fn execute(
+        self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        pool: &mut TransientPool,
+    ) -> Result<(), GraphError> {
+        let schedule = self.compile()?;
+
+        pool.allocate(device, &schedule);
+
+        let resources = self
+            .resources
+            .iter()
+            .enumerate()
+            .filter_map(|(index, resource)| {
+                let id = ResourceId(index);
+
+                let physical = match resource.source {
+                    ResourceSource::ImportedTexture(view) => PhysicalResource::Texture(view),
+                    ResourceSource::ImportedBuffer(buffer) => PhysicalResource::Buffer(buffer),
+                    ResourceSource::Transient(_) => {
+                        let &(description, slot) = schedule.slots.get(&id)?;
+                        pool.get(description, slot)
+                    }
+                };
+
+                Some((id, physical))
+            })
+            .collect::<HashMap<_, _>>();
+
+        let mut passes = self.passes.into_iter().map(Some).collect::<Vec<_>>();
+
+        for &index in &schedule.order {
+            let pass = passes[index].take().expect("every pass is scheduled once");
+
+            encoder.push_debug_group(&pass.name);
+
+            (pass.execute)(&mut PassContext {
+                device,
+                encoder,
+                resources: &resources,
+            });
+
+            encoder.pop_debug_group();
+        }
+
+        Ok(())
+    }
+
+    /// Describes the graph in the Graphviz DOT language, with every pass pointing to the
+    /// resources it writes and every resource pointing to the passes reading it.
+    ///
+    /// Culled passes and transient resources are dashed.
+    pub fn to_dot(&self) -> String {
+        let live = self
+            .dependencies()
+            .map(|dependencies| self.live_passes(&dependencies))
+            .unwrap_or_default();
+
+        let mut dot = String::from("digraph render_graph {\n    rankdir=LR;\n");
+
+        for (index, pass) in self.passes.iter().enumerate() {
+            let style = if live.contains(&index) {
+                "solid"
+            } else {
+                "dashed"
+            };
+
+            let _ = writeln!(
+                dot,
+                "    pass{index} [label={:?}, shape=box, style={style}];",
+                pass.name
+            );
+        }
+
+        for (index, resource) in self.resources.iter().enumerate() {
+            let style = match resource.source {
+                ResourceSource::Transient(_) => "dashed",
+                _ => "solid",
+            };
+
+            let _ = writeln!(
+                dot,
+                "    resource{index} [label={:?}, shape=ellipse, style={style}];",
+                resource.name
+            );
+        }
+
+        for (index, pass) in self.passes.iter().enumerate() {
+            for ResourceId(resource) in &pass.accesses.reads {
+                let _ = writeln!(dot, "    resource{resource} -> pass{index};");
+            }
+
+            for ResourceId(resource) in &pass.accesses.writes {
+                let _ = writeln!(dot, "    pass{index} -> resource{resource};");
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+impl PassBuilder {
+    /// Declares that the pass reads the given resource.
+    pub fn read(&mut self, resource: impl Into<ResourceId>) -> &mut Self {
+        self.reads.push(resource.into());
+        self
+    }
+
+    /// Declares that the pass writes the given resource (possibly reading its previous contents
+    /// too, such as when loading a render target).
+    pub fn write(&mut self, resource: impl Into<ResourceId>) -> &mut Self {
+        self.writes.push(resource.into());
+        self
+    }
+
+    /// Declares that the pass affects something outside the graph (such as reading back a
+    /// texture), so that it is never culled.
+    pub fn side_effects(&mut self) -> &mut Self {
+        self.side_effects = true;
+        self
+    }
+
+    /// Returns whether the pass reads or writes the given resource.
+    fn accesses(&self, resource: ResourceId) -> bool {
+        self.reads.contains(&resource) || self.writes.contains(&resource)
+    }
+}
+
+impl<'p> PassContext<'p> {
+    /// Returns a view into the whole texture backing the given handle.
+    pub fn texture(&self, handle: TextureHandle) -> &'p TextureView {
+        match self.resources[&handle.0] {
+            PhysicalResource::Texture(view) => view,
+            PhysicalResource::Buffer(_) => unreachable!("texture handles refer to textures"),
+        }
+    }
+
+    /// Returns the buffer backing the given handle.
+    pub fn buffer(&self, handle: BufferHandle) -> &'p Buffer {
+        match self.resources[&handle.0] {
+            PhysicalResource::Buffer(buffer) => buffer,
+            PhysicalResource::Texture(_) => unreachable!("buffer handles refer to buffers"),
+        }
+    }
+}
+
+impl Schedule {
+    /// Returns the number of textures and buffers allocated for transient resources, which is
+    /// lower than the number of transient resources whenever some of them are aliased.
+    pub fn transient_slots(&self) -> usize {
+        self.slots.values().collect::<HashSet<_>>().len()
+    }
+}
+
+impl TransientPool {
+    /// Creates every texture and buffer needed by the schedule which hasn't been created yet,
+    /// dropping the ones which are no longer needed.
+    fn allocate(&mut self, device: &Device, schedule: &Schedule) {
+        let mut texture_counts = HashMap::<TransientTexture, usize>::new();
+        let mut buffer_counts = HashMap::<TransientBuffer, usize>::new();
+
+        for &(description, slot) in schedule.slots.values() {
+            match description {
+                TransientResource::Texture(texture) => {
+                    let count = texture_counts.entry(texture).or_default();
+                    *count = (*count).max(slot + 1);
+                }
+                TransientResource::Buffer(buffer) => {
+                    let count = buffer_counts.entry(buffer).or_default();
+                    *count = (*count).max(slot + 1);
+                }
+            }
+        }
+
+        self.textures
+            .retain(|description, _| texture_counts.contains_key(description));
+        self.buffers
+            .retain(|description, _| buffer_counts.contains_key(description));
+
+        for (description, count) in texture_counts {
+            let textures = self.textures.entry(description).or_default();
+
+            textures.truncate(count);
+
+            while textures.len() < count {
+                let texture = device.create_texture(&TextureDescriptor {
+                    label: Some("TransientPool::texture"),
+                    size: Extent3d {
+                        width: description.width,
+                        height: description.height,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format: description.format,
+                    usage: description.usage,
+                    view_formats: &[],
+                });
+
+                let view = texture.create_view(&TextureViewDescriptor::default());
+
+                textures.push((texture, view));
+            }
+        }
+
+        for (description, count) in buffer_counts {
+            let buffers = self.buffers.entry(description).or_default();
+
+            buffers.truncate(count);
+
+            while buffers.len() < count {
+                buffers.push(device.create_buffer(&BufferDescriptor {
+                    label: Some("TransientPool::buffer"),
+                    size: description.size,
+                    usage: description.usage,
+                    mapped_at_creation: false,
+                }));
+            }
+        }
+    }
+
+    /// Returns the physical resource allocated in the given slot.
+    fn get(&self, description: TransientResource, slot: usize) -> PhysicalResource<'_> {
+        match description {
+            TransientResource::Texture(texture) => {
+                PhysicalResource::Texture(&self.textures[&texture][slot].1)
+            }
+            TransientResource::Buffer(buffer) => {
+                PhysicalResource::Buffer(&self.buffers[&buffer][slot])
+            }
+        }
+    }
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UninitializedRead { pass, resource } => write!(
+                f,
+                "the pass {pass} reads {resource} before any pass has written it"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GraphError {}
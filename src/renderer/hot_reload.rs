@@ -0,0 +1,69 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+};
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::renderer::shaders::ShaderKind;
+
+/// Watches a directory of shader sources, reporting which shaders have changed on disk.
+pub struct ShaderWatcher {
+    /// The directory being watched.
+    pub directory: PathBuf,
+    /// The underlying file system watcher, which stops watching once dropped.
+    _watcher: RecommendedWatcher,
+    /// Receives every file system event within the watched directory.
+    events: Receiver<notify::Result<Event>>,
+}
+
+impl ShaderWatcher {
+    /// Starts watching the given directory.
+    pub fn new(directory: &Path) -> anyhow::Result<Self> {
+        let (sender, events) = mpsc::channel();
+
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = sender.send(event);
+        })?;
+
+        watcher.watch(directory, RecursiveMode::NonRecursive)?;
+
+        Ok(Self {
+            directory: directory.to_owned(),
+            _watcher: watcher,
+            events,
+        })
+    }
+
+    /// Returns every shader whose source has been modified since the last call.
+    ///
+    /// Changes to any other WGSL file (which may be included by any shader) mark every shader as
+    /// changed.
+    pub fn changed_shaders(&self) -> HashSet<ShaderKind> {
+        let changed_files = self
+            .events
+            .try_iter()
+            .filter_map(|event| match event {
+                Ok(event) => Some(event),
+                Err(e) => {
+                    log::warn!("error while watching shaders: {e}");
+                    None
+                }
+            })
+            .filter(|event| event.kind.is_create() || event.kind.is_modify())
+            .flat_map(|event| event.paths)
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "wgsl")
+            })
+            .filter_map(|path| Some(path.file_name()?.to_str()?.to_owned()))
+            .collect::<HashSet<_>>();
+
+        changed_files
+            .iter()
+            .map(|name| ShaderKind::from_file_name(name))
+            .collect::<Option<HashSet<_>>>()
+            .unwrap_or_else(|| ShaderKind::ALL.into())
+    }
+}
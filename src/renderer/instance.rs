@@ -0,0 +1,117 @@
+use std::marker::PhantomData;
+
+use bytemuck::{Pod, Zeroable};
+use glam::{Mat4, Vec4};
+use wgpu::*;
+
+use crate::renderer::mesh::VertexLayout;
+
+/// A single copy of a mesh, drawn along with every other copy in one draw call.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+pub struct MeshInstance {
+    /// Transforms the vertices of the mesh into world space.
+    pub transform: Mat4,
+    /// The (linear) color the base color of the mesh is multiplied by, with alpha.
+    pub color: Vec4,
+}
+
+/// A vertex buffer holding one element per instance, which grows as more instances are written
+/// into it.
+pub struct InstanceBuffer<T> {
+    /// The label of the buffer, reused whenever it grows.
+    label: String,
+    /// The buffer holding the instances, with room for `capacity` of them.
+    buffer: Buffer,
+    /// The number of instances the `buffer` has room for.
+    capacity: usize,
+    /// The number of instances most recently written into the `buffer`.
+    len: usize,
+    /// The type of every instance.
+    _instance: PhantomData<T>,
+}
+
+impl MeshInstance {
+    /// Creates an instance with the given transform, keeping the colors of the mesh.
+    pub fn new(transform: Mat4) -> Self {
+        Self {
+            transform,
+            color: Vec4::ONE,
+        }
+    }
+}
+
+impl Default for MeshInstance {
+    fn default() -> Self {
+        Self::new(Mat4::IDENTITY)
+    }
+}
+
+impl VertexLayout for MeshInstance {
+    // matrices can't be vertex inputs, so the transform is passed as its four columns
+    const ATTRIBUTES: &'static [VertexAttribute] = &vertex_attr_array![
+        4 => Float32x4,
+        5 => Float32x4,
+        6 => Float32x4,
+        7 => Float32x4,
+        8 => Float32x4,
+    ];
+}
+
+impl<T: Pod> InstanceBuffer<T> {
+    /// Creates an empty instance buffer, with room for a single instance.
+    pub fn new(device: &Device, label: &str) -> Self {
+        Self {
+            label: label.to_owned(),
+            buffer: Self::create_buffer(device, label, 1),
+            capacity: 1,
+            len: 0,
+            _instance: PhantomData,
+        }
+    }
+
+    /// Creates a buffer with room for the given number of instances.
+    fn create_buffer(device: &Device, label: &str, capacity: usize) -> Buffer {
+        device.create_buffer(&BufferDescriptor {
+            label: Some(label),
+            size: (capacity * size_of::<T>()) as _,
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Replaces the contents of the buffer with the given instances, recreating it with room for
+    /// the next power of two instances if they don't fit.
+    pub fn write(&mut self, device: &Device, queue: &Queue, instances: &[T]) {
+        if instances.len() > self.capacity {
+            self.capacity = instances.len().next_power_of_two();
+            self.buffer = Self::create_buffer(device, &self.label, self.capacity);
+        }
+
+        if !instances.is_empty() {
+            queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(instances));
+        }
+
+        self.len = instances.len();
+    }
+
+    /// Returns the buffer holding the instances.
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    /// Returns the number of instances most recently written into the buffer.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether no instances were written into the buffer.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of instances the buffer has room for before growing.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
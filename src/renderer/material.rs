@@ -0,0 +1,131 @@
+use bytemuck::{Pod, Zeroable};
+use glam::{Vec3, Vec4};
+use wgpu::{util::DeviceExt, *};
+
+/// The CPU side description of a physically based (metallic-roughness) material.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaterialData {
+    /// The name of the material, if it has one.
+    pub name: Option<String>,
+
+    /// The linear base color (and alpha) of the material, multiplied with the base color texture.
+    pub base_color_factor: Vec4,
+    /// How metallic the material is (from 0.0 to 1.0).
+    pub metallic_factor: f32,
+    /// How rough the material is (from 0.0 to 1.0).
+    pub roughness_factor: f32,
+    /// The linear color of the light emitted by the material.
+    pub emissive_factor: Vec3,
+
+    /// The index of the (sRGB) base color texture within the model.
+    pub base_color_texture: Option<usize>,
+    /// The index of the (linear) texture holding roughness in green and metalness in blue.
+    pub metallic_roughness_texture: Option<usize>,
+    /// The index of the (linear) tangent space normal map.
+    pub normal_texture: Option<usize>,
+    /// The index of the (sRGB) emissive texture.
+    pub emissive_texture: Option<usize>,
+}
+
+/// The layout of a material's uniform buffer, matching `Material` in the mesh shader.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+struct MaterialUniform {
+    base_color: Vec4,
+    emissive: Vec3,
+    metallic: f32,
+    roughness: f32,
+    _padding: [f32; 3],
+}
+
+/// Identifies a material which has been uploaded to the renderer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MaterialId(pub(crate) usize);
+
+/// A material stored on the GPU.
+pub struct Material {
+    /// The uniform buffer holding the material's parameters.
+    pub buffer: Buffer,
+    /// The bind group holding the `buffer`, the material's textures and their sampler.
+    pub bind_group: BindGroup,
+}
+
+/// The GPU resources sampled by a material.
+pub struct MaterialTextures<'a> {
+    /// The (sRGB) base color texture.
+    pub base_color: &'a TextureView,
+    /// The (sRGB) emissive texture.
+    pub emissive: &'a TextureView,
+    /// The sampler used for all textures.
+    pub sampler: &'a Sampler,
+}
+
+impl Default for MaterialData {
+    fn default() -> Self {
+        Self {
+            name: None,
+            base_color_factor: Vec4::ONE,
+            metallic_factor: 0.0,
+            roughness_factor: 1.0,
+            emissive_factor: Vec3::ZERO,
+            base_color_texture: None,
+            metallic_roughness_texture: None,
+            normal_texture: None,
+            emissive_texture: None,
+        }
+    }
+}
+
+impl MaterialId {
+    /// The plain white material every renderer starts with.
+    pub const DEFAULT: Self = Self(0);
+}
+
+impl Material {
+    /// Uploads the given [`MaterialData`] into a new [`Material`], sampling the given textures.
+    pub fn new(
+        device: &Device,
+        layout: &BindGroupLayout,
+        data: &MaterialData,
+        textures: MaterialTextures,
+    ) -> Self {
+        let uniform = MaterialUniform {
+            base_color: data.base_color_factor,
+            emissive: data.emissive_factor,
+            metallic: data.metallic_factor,
+            roughness: data.roughness_factor,
+            _padding: [0.0; 3],
+        };
+
+        let buffer = device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some("Material::buffer"),
+            contents: bytemuck::bytes_of(&uniform),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Material::bind_group"),
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::TextureView(textures.base_color),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: BindingResource::TextureView(textures.emissive),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: BindingResource::Sampler(textures.sampler),
+                },
+            ],
+        });
+
+        Self { buffer, bind_group }
+    }
+}
@@ -0,0 +1,218 @@
+use std::{collections::HashMap, ops::Range};
+
+use bytemuck::{Pod, Zeroable};
+use glam::{Vec2, Vec3, vec2, vec3};
+use wgpu::{util::DeviceExt, *};
+
+use crate::renderer::instance::{InstanceBuffer, MeshInstance};
+
+/// A type which can be stored in a vertex buffer, describing how it is laid out in memory.
+pub trait VertexLayout: Pod {
+    /// The attributes of the vertex, in the order of their shader locations.
+    const ATTRIBUTES: &'static [VertexAttribute];
+
+    /// Returns the layout of a vertex buffer holding this type.
+    fn layout() -> VertexBufferLayout<'static> {
+        VertexBufferLayout {
+            array_stride: size_of::<Self>() as _,
+            step_mode: VertexStepMode::Vertex,
+            attributes: Self::ATTRIBUTES,
+        }
+    }
+}
+
+/// The vertex used by all meshes drawn with the mesh pipeline.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Pod, Zeroable)]
+pub struct Vertex {
+    /// The position of the vertex in world space.
+    pub position: Vec3,
+    /// The surface normal at the vertex.
+    pub normal: Vec3,
+    /// The texture coordinates of the vertex.
+    pub tex_coords: Vec2,
+    /// The color of the vertex (in linear space).
+    pub color: Vec3,
+}
+
+impl VertexLayout for Vertex {
+    const ATTRIBUTES: &'static [VertexAttribute] = &vertex_attr_array![
+        0 => Float32x3,
+        1 => Float32x3,
+        2 => Float32x2,
+        3 => Float32x3,
+    ];
+}
+
+/// The CPU side geometry of a mesh, before being uploaded to the GPU.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MeshData {
+    /// The vertices of the mesh.
+    pub vertices: Vec<Vertex>,
+    /// The indices into `vertices`, with every three forming a triangle.
+    pub indices: Vec<u32>,
+}
+
+/// Identifies a mesh which has been uploaded to the renderer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MeshId(pub(crate) usize);
+
+/// An indexed mesh stored on the GPU, drawn once for every one of its instances.
+pub struct Mesh {
+    /// The buffer holding all vertices of the mesh.
+    pub vertex_buffer: Buffer,
+    /// The buffer holding all (`u32`) indices of the mesh.
+    pub index_buffer: Buffer,
+
+    /// The total number of indices in the `index_buffer`.
+    pub index_count: u32,
+    /// The range of indices drawn, which covers the whole mesh by default.
+    pub draw_range: Range<u32>,
+
+    /// Every copy of the mesh drawn, which is a single untransformed copy by default.
+    pub instances: Vec<MeshInstance>,
+    /// The buffer the `instances` are uploaded into before drawing.
+    instance_buffer: InstanceBuffer<MeshInstance>,
+}
+
+impl MeshData {
+    /// Returns a single triangle facing the positive z axis, with a red, green and blue corner.
+    pub fn triangle() -> Self {
+        let corners = [
+            (vec2(0.5, -0.5), vec3(0.0, 1.0, 0.0), vec2(1.0, 1.0)),
+            (vec2(-0.5, -0.5), vec3(1.0, 0.0, 0.0), vec2(0.0, 1.0)),
+            (vec2(0.0, 0.5), vec3(0.0, 0.0, 1.0), vec2(0.5, 0.0)),
+        ];
+
+        let vertices = corners
+            .into_iter()
+            .map(|(position, color, tex_coords)| Vertex {
+                position: position.extend(0.0),
+                normal: Vec3::Z,
+                tex_coords,
+                color,
+            })
+            .collect();
+
+        Self {
+            vertices,
+            indices: vec![0, 1, 2],
+        }
+    }
+
+    /// Replaces all normals with the normal of the face each vertex belongs to.
+    ///
+    /// As vertices shared between faces can only hold one normal, every triangle is given its own
+    /// three vertices.
+    pub fn generate_flat_normals(&mut self) {
+        let mut vertices = Vec::with_capacity(self.indices.len());
+
+        for triangle in self.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| self.vertices[triangle[i] as usize]);
+            let normal = (b.position - a.position)
+                .cross(c.position - a.position)
+                .normalize_or_zero();
+
+            vertices.extend([a, b, c].map(|vertex| Vertex { normal, ..vertex }));
+        }
+
+        self.indices = (0..vertices.len() as u32).collect();
+        self.vertices = vertices;
+    }
+
+    /// Replaces all normals with the average normal of the faces surrounding each vertex,
+    /// weighted by their area.
+    ///
+    /// Vertices sharing a position are treated as one, so seams in texture coordinates don't
+    /// show up as seams in the shading.
+    pub fn generate_smooth_normals(&mut self) {
+        let key = |position: Vec3| position.to_array().map(f32::to_bits);
+        let mut normals = HashMap::<[u32; 3], Vec3>::new();
+
+        for triangle in self.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| self.vertices[triangle[i] as usize].position);
+
+            // the length of the cross product is proportional to the area of the face
+            let normal = (b - a).cross(c - a);
+
+            for position in [a, b, c] {
+                *normals.entry(key(position)).or_default() += normal;
+            }
+        }
+
+        for vertex in &mut self.vertices {
+            vertex.normal = normals
+                .get(&key(vertex.position))
+                .map_or(Vec3::ZERO, |normal| normal.normalize_or_zero());
+        }
+    }
+}
+
+impl Mesh {
+    /// Uploads the given vertices and indices into a new [`Mesh`].
+    pub fn new<V: VertexLayout>(
+        device: &Device,
+        label: &str,
+        vertices: &[V],
+        indices: &[u32],
+    ) -> Self {
+        let vertex_buffer = device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some(&format!("{label}::vertex_buffer")),
+            contents: bytemuck::cast_slice(vertices),
+            usage: BufferUsages::VERTEX,
+        });
+
+        let index_buffer = device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some(&format!("{label}::index_buffer")),
+            contents: bytemuck::cast_slice(indices),
+            usage: BufferUsages::INDEX,
+        });
+
+        let index_count = indices.len() as u32;
+
+        Self {
+            vertex_buffer,
+            index_buffer,
+            index_count,
+            draw_range: 0..index_count,
+            instances: vec![MeshInstance::default()],
+            instance_buffer: InstanceBuffer::new(device, &format!("{label}::instance_buffer")),
+        }
+    }
+
+    /// Uploads the given [`MeshData`] into a new [`Mesh`].
+    pub fn from_data(device: &Device, label: &str, data: &MeshData) -> Self {
+        Self::new(device, label, &data.vertices, &data.indices)
+    }
+
+    /// Uploads the current `instances`, growing the instance buffer if needed.
+    pub fn write_instances(&mut self, device: &Device, queue: &Queue) {
+        self.instance_buffer.write(device, queue, &self.instances);
+    }
+
+    /// Returns the number of instances which were last uploaded.
+    pub fn instance_count(&self) -> u32 {
+        self.instance_buffer.len() as u32
+    }
+
+    /// Returns the number of instances the instance buffer has room for before growing.
+    pub fn instance_capacity(&self) -> usize {
+        self.instance_buffer.capacity()
+    }
+
+    /// Binds the mesh's buffers and draws its `draw_range` with the given range of the uploaded
+    /// instances.
+    ///
+    /// Empty meshes are skipped, as their (empty) buffers can't be bound.
+    pub fn draw(&self, pass: &mut RenderPass, instances: Range<u32>) {
+        if self.index_count == 0 {
+            return;
+        }
+
+        pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        pass.set_vertex_buffer(1, self.instance_buffer.buffer().slice(..));
+        pass.set_index_buffer(self.index_buffer.slice(..), IndexFormat::Uint32);
+
+        pass.draw_indexed(self.draw_range.clone(), 0, instances);
+    }
+}
@@ -1,33 +1,115 @@
 pub mod camera;
+pub mod capture;
+pub mod compute;
+pub mod config;
+pub mod depth;
+pub mod graph;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod hot_reload;
+pub mod instance;
+pub mod material;
+pub mod mesh;
+pub mod model;
+pub mod msaa;
+pub mod particles;
 pub mod pipelines;
+pub mod post;
+pub mod resources;
 pub mod shaders;
+pub mod target;
+pub mod texture;
+pub mod tonemap;
 
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use anyhow::{Context, bail};
+use image::{Rgba, RgbaImage};
 
 use wgpu::*;
 use winit::{dpi::PhysicalSize, window::Window};
 
-use crate::renderer::{camera::Camera, pipelines::Pipelines, shaders::Shaders};
+use crate::renderer::{
+    camera::Camera,
+    capture::{FrameCapture, PendingCapture},
+    compute::ComputeDispatch,
+    config::RendererConfig,
+    depth::DepthTexture,
+    graph::{RenderGraph, TextureHandle, TransientPool},
+    material::{Material, MaterialData, MaterialId, MaterialTextures},
+    mesh::{Mesh, MeshData, MeshId},
+    model::Model,
+    msaa::MsaaTexture,
+    particles::{EmitterId, EmitterSettings, Particle, ParticleEmitter},
+    pipelines::{Pipelines, TargetFormats},
+    post::{ColorLut, PostProcessing, PostSettings},
+    resources::{ResourceSources, TextureSource},
+    shaders::{ShaderError, ShaderKind, Shaders, preprocessor::ShaderFiles},
+    target::{RenderTarget, SurfaceErrorCounts},
+    texture::{ColorSpace, ImageTexture, SamplerCache, TextureId},
+    tonemap::{HdrTarget, TonemapSettings},
+};
 
 /// Manages all GPU state and renders all game content.
 #[allow(unused)]
 pub struct Renderer {
+    /// The instance the adapter and `target` were created from.
+    instance: Instance,
+    /// The configuration the renderer was created with, reused when recreating the device.
+    config: RendererConfig,
+    /// Describes the adapter the `device` was created from.
+    adapter_info: AdapterInfo,
     /// A handle to the physical device used to render (usually the GPU).
     pub device: Device,
     /// A queue by which commands are sent to the rendering device.
     pub queue: Queue,
+    /// Set by the `device` once it has been lost, after which everything is recreated.
+    device_lost: Arc<AtomicBool>,
 
-    /// The window being rendered onto.
-    window: Arc<Window>,
-    /// The primary surface texture being rendered onto.
-    pub surface: Surface<'static>,
-    /// The configuration of the `surface`.
+    /// The target being rendered onto (either a window's surface or an offscreen texture).
+    pub target: RenderTarget,
+    /// The configuration (size and format) of the `target`.
     pub surface_config: SurfaceConfiguration,
+    /// Every present mode the `target` supports.
+    present_modes: Vec<PresentMode>,
+    /// How often acquiring a frame from the `target` has failed.
+    surface_errors: SurfaceErrorCounts,
+    /// The depth texture used by the main render pass, matching the size of the `target`.
+    depth_texture: DepthTexture,
+    /// The color texture the main render pass draws into when multisampling, resolved onto the
+    /// `target`.
+    msaa_texture: Option<MsaaTexture>,
+    /// The HDR texture the main render pass draws into, tonemapped onto the `target`.
+    hdr_target: HdrTarget,
+    /// How the HDR scene is mapped onto the `target`.
+    pub tonemap_settings: TonemapSettings,
+    /// The textures used for applying post-processing effects, matching the size of the `target`.
+    post: PostProcessing,
+    /// Which post-processing effects are applied, and how.
+    pub post_settings: PostSettings,
+    /// The lookup table colors are mapped through by color grading.
+    color_lut: ColorLut,
+    /// Every sample count supported by the device for the target's formats.
+    sample_counts: Vec<u32>,
+    /// The textures and buffers allocated for the transient resources of the render graph,
+    /// reused across frames.
+    transient_pool: TransientPool,
 
     /// All shaders used in the rendering process.
     shaders: Shaders,
     /// All (compute and render) pipelines and bind group layouts used in the application.
     pipelines: Pipelines,
+    /// Watches the shader sources on disk for changes, once enabled.
+    #[cfg(not(target_arch = "wasm32"))]
+    shader_watcher: Option<hot_reload::ShaderWatcher>,
+    /// The error of the most recent reload of every shader which failed to reload.
+    shader_errors: HashMap<ShaderKind, ShaderError>,
 
     /// Manages rendering egui content.
     ui_renderer: egui_wgpu::Renderer,
@@ -36,40 +118,299 @@ pub struct Renderer {
     camera_bind_group: BindGroup,
     /// The uniform buffer holding the camera's view-projection matrix.
     camera_buffer: Buffer,
+
+    /// The compute dispatches queued to run before the main render pass of the next frame.
+    compute_dispatches: Vec<ComputeDispatch>,
+
+    /// All meshes uploaded to the renderer along with their material, drawn every frame.
+    meshes: Vec<(Mesh, MaterialId)>,
+    /// All particle emitters added to the renderer, simulated and drawn every frame.
+    emitters: Vec<ParticleEmitter>,
+    /// All materials uploaded to the renderer, starting with the default material.
+    materials: Vec<Material>,
+    /// All textures uploaded to the renderer, starting with a single white pixel.
+    textures: Vec<ImageTexture>,
+    /// All samplers used by the renderer.
+    samplers: SamplerCache,
+    /// The CPU side copies of all uploaded resources, re-uploaded if the device is lost.
+    sources: ResourceSources,
+
+    /// Whether the next rendered frame should be read back from the GPU.
+    capture_requested: bool,
+    /// The most recently captured frame which hasn't been taken yet.
+    last_capture: Option<FrameCapture>,
+    /// Whether the render graph of the next rendered frame should be dumped.
+    graph_dump_requested: bool,
+    /// The most recently dumped render graph (in the Graphviz DOT language) which hasn't been
+    /// taken yet.
+    last_graph_dump: Option<String>,
+}
+
+/// The tessellated UI of a frame, along with the textures to free once it has been drawn.
+struct UiFrame {
+    /// The triangles making up the UI.
+    tris: Vec<egui::ClippedPrimitive>,
+    /// The size of the target the UI is drawn onto.
+    screen_descriptor: egui_wgpu::ScreenDescriptor,
+    /// The UI textures which are no longer used.
+    free: Vec<egui::TextureId>,
 }
 
 impl Renderer {
-    /// Initializes the rendering context, creating a new [`Renderer`].
-    pub async fn new(window: Arc<Window>) -> anyhow::Result<Self> {
-        let instance = Instance::new(&InstanceDescriptor {
-            backends: Backends::PRIMARY,
-            ..Default::default()
-        });
+    /// Initializes the rendering context, creating a new [`Renderer`] targetting the given window.
+    ///
+    /// Fails if no adapter is found, or the adapter doesn't support the configuration.
+    pub async fn new(window: Arc<Window>, config: RendererConfig) -> anyhow::Result<Self> {
+        let instance = Instance::new(&config.instance_descriptor());
 
         let surface = instance.create_surface(Arc::clone(&window))?;
 
-        let adapter = instance
-            .request_adapter(&RequestAdapterOptions {
-                power_preference: PowerPreference::HighPerformance,
-                force_fallback_adapter: false,
-                compatible_surface: Some(&surface),
-            })
-            .await?;
+        let adapter = config.request_adapter(&instance, Some(&surface)).await?;
+
+        config.check_adapter(&adapter)?;
 
-        let (device, queue) = adapter.request_device(&DeviceDescriptor::default()).await?;
+        let capabilities = surface.get_capabilities(&adapter);
+
+        let format = RenderTarget::choose_format(&capabilities.formats, config.surface_format)
+            .context("the surface is not supported by the adapter")?;
+
+        // the automatic modes are always supported, falling back to whatever is available
+        let present_modes = [PresentMode::AutoVsync, PresentMode::AutoNoVsync]
+            .into_iter()
+            .chain(capabilities.present_modes)
+            .collect::<Vec<_>>();
+
+        let formats = TargetFormats {
+            color: HdrTarget::FORMAT,
+            depth: DepthTexture::DEFAULT_FORMAT,
+            sample_count: config.sample_count,
+            output: format,
+        };
+
+        config.check_target(&adapter, formats, &present_modes)?;
+
+        let (device, queue) = adapter.request_device(&config.device_descriptor()).await?;
+
+        let mut surface_config = Self::get_surface_config(window.inner_size(), format);
+
+        // allows frames to be read back, if the surface supports it
+        surface_config.usage |= capabilities.usages & TextureUsages::COPY_SRC;
+        surface_config.present_mode = config.present_mode;
 
-        let surface_config = Self::get_surface_config(&window);
         surface.configure(&device, &surface_config);
 
+        let target = RenderTarget::Window { window, surface };
+
+        Ok(Self::with_target(
+            instance,
+            config,
+            &adapter,
+            (device, queue),
+            target,
+            surface_config,
+            present_modes,
+        ))
+    }
+
+    /// Initializes a rendering context without any window, creating a new [`Renderer`] which
+    /// draws into an offscreen texture of the given size.
+    ///
+    /// The present mode of the configuration is ignored, as nothing is ever presented. Machines
+    /// without a GPU usually need [`RendererConfig::headless`] for an adapter to be found.
+    pub async fn new_headless(
+        size: PhysicalSize<u32>,
+        config: RendererConfig,
+    ) -> anyhow::Result<Self> {
+        let instance = Instance::new(&config.instance_descriptor());
+
+        let adapter = config.request_adapter(&instance, None).await?;
+
+        config.check_adapter(&adapter)?;
+
+        let surface_config = SurfaceConfiguration {
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+            ..Self::get_surface_config(size, TextureFormat::Bgra8UnormSrgb)
+        };
+
+        let present_modes = vec![surface_config.present_mode];
+
+        let formats = TargetFormats {
+            color: HdrTarget::FORMAT,
+            depth: DepthTexture::DEFAULT_FORMAT,
+            sample_count: config.sample_count,
+            output: surface_config.format,
+        };
+
+        let config = RendererConfig {
+            present_mode: surface_config.present_mode,
+            ..config
+        };
+
+        config.check_target(&adapter, formats, &present_modes)?;
+
+        let (device, queue) = adapter.request_device(&config.device_descriptor()).await?;
+
+        let target = RenderTarget::offscreen(&device, &surface_config);
+
+        Ok(Self::with_target(
+            instance,
+            config,
+            &adapter,
+            (device, queue),
+            target,
+            surface_config,
+            present_modes,
+        ))
+    }
+
+    /// Creates all GPU resources needed to render onto an already configured target.
+    fn with_target(
+        instance: Instance,
+        config: RendererConfig,
+        adapter: &Adapter,
+        (device, queue): (Device, Queue),
+        target: RenderTarget,
+        surface_config: SurfaceConfiguration,
+        present_modes: Vec<PresentMode>,
+    ) -> Self {
+        let formats = TargetFormats {
+            color: HdrTarget::FORMAT,
+            depth: DepthTexture::DEFAULT_FORMAT,
+            sample_count: config.sample_count,
+            output: surface_config.format,
+        };
+
+        let sample_counts =
+            MsaaTexture::supported_sample_counts(adapter, device.features(), formats);
+
         let shaders = Shaders::new(&device);
-        let pipelines = Pipelines::new(&device, &shaders);
+        let pipelines = Pipelines::new(&device, &shaders, formats);
+
+        let mut samplers = SamplerCache::default();
+        let color_lut = ColorLut::identity(&device, &queue);
+
+        let (depth_texture, msaa_texture, hdr_target, post) = Self::create_frame_textures(
+            &device,
+            &surface_config,
+            &pipelines,
+            &mut samplers,
+            &color_lut,
+        );
 
         let ui_renderer = egui_wgpu::Renderer::new(
             &device,
-            TextureFormat::Bgra8Unorm,
+            surface_config.format,
             egui_wgpu::RendererOptions::default(),
         );
 
+        let (camera_buffer, camera_bind_group) = Self::create_camera_buffer(&device, &pipelines);
+        let device_lost = Self::watch_device_lost(&device);
+
+        let mut renderer = Self {
+            instance,
+            config,
+            adapter_info: adapter.get_info(),
+            device,
+            queue,
+            device_lost,
+            target,
+            surface_config,
+            present_modes,
+            surface_errors: SurfaceErrorCounts::default(),
+            depth_texture,
+            msaa_texture,
+            hdr_target,
+            tonemap_settings: TonemapSettings::default(),
+            post,
+            post_settings: PostSettings::default(),
+            color_lut,
+            sample_counts,
+            transient_pool: TransientPool::default(),
+            shaders,
+            pipelines,
+            #[cfg(not(target_arch = "wasm32"))]
+            shader_watcher: None,
+            shader_errors: HashMap::new(),
+            ui_renderer,
+            camera_bind_group,
+            camera_buffer,
+            compute_dispatches: Vec::new(),
+            meshes: Vec::new(),
+            emitters: Vec::new(),
+            materials: Vec::new(),
+            textures: Vec::new(),
+            samplers,
+            sources: ResourceSources::default(),
+            capture_requested: false,
+            last_capture: None,
+            graph_dump_requested: false,
+            last_graph_dump: None,
+        };
+
+        // the first texture and material become `TextureId::WHITE` and `MaterialId::DEFAULT`
+        let white = RgbaImage::from_pixel(1, 1, Rgba([255; 4]));
+
+        renderer.upload_texture(&white, ColorSpace::Srgb);
+        renderer.upload_material(&MaterialData::default(), &[]);
+
+        renderer
+    }
+
+    /// Creates the depth, HDR (and multisampled color, if needed) textures rendered into by the
+    /// main render pass, along with the textures used for post-processing, matching the size of
+    /// the target.
+    fn create_frame_textures(
+        device: &Device,
+        surface_config: &SurfaceConfiguration,
+        pipelines: &Pipelines,
+        samplers: &mut SamplerCache,
+        color_lut: &ColorLut,
+    ) -> (DepthTexture, Option<MsaaTexture>, HdrTarget, PostProcessing) {
+        let SurfaceConfiguration { width, height, .. } = *surface_config;
+        let formats = pipelines.formats();
+
+        let depth_texture =
+            DepthTexture::new(device, formats.depth, width, height, formats.sample_count);
+
+        let msaa_texture = (formats.sample_count > 1)
+            .then(|| MsaaTexture::new(device, formats.color, width, height, formats.sample_count));
+
+        let sampler = samplers.screen_sampler(device);
+
+        let hdr_target = HdrTarget::new(device, pipelines, &sampler, width, height);
+
+        let post = PostProcessing::new(
+            device,
+            pipelines,
+            &sampler,
+            (&hdr_target, color_lut),
+            width,
+            height,
+        );
+
+        (depth_texture, msaa_texture, hdr_target, post)
+    }
+
+    /// Recreates the textures rendered into by the main render pass, after the size or formats of
+    /// the target have changed.
+    fn recreate_frame_textures(&mut self) {
+        (
+            self.depth_texture,
+            self.msaa_texture,
+            self.hdr_target,
+            self.post,
+        ) = Self::create_frame_textures(
+            &self.device,
+            &self.surface_config,
+            &self.pipelines,
+            &mut self.samplers,
+            &self.color_lut,
+        );
+    }
+
+    /// Creates the uniform buffer holding the camera's view-projection matrix, along with the
+    /// bind group holding it.
+    fn create_camera_buffer(device: &Device, pipelines: &Pipelines) -> (Buffer, BindGroup) {
         let camera_buffer = device.create_buffer(&BufferDescriptor {
             label: Some("Renderer::camera_buffer"),
             size: size_of::<glam::Mat4>() as _,
@@ -86,32 +427,50 @@ impl Renderer {
             }],
         });
 
-        Ok(Self {
-            device,
-            queue,
-            window,
-            surface,
-            surface_config,
-            shaders,
-            pipelines,
-            ui_renderer,
-            camera_bind_group,
-            camera_buffer,
-        })
+        (camera_buffer, camera_bind_group)
     }
 
-    /// Renders all world content onto the surface.
+    /// Returns a flag which is set once the given device is lost.
+    ///
+    /// Every device gets its own flag, so that a replaced device being dropped can't mark its
+    /// replacement as lost.
+    fn watch_device_lost(device: &Device) -> Arc<AtomicBool> {
+        let lost = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&lost);
+
+        device.set_device_lost_callback(move |reason, message| {
+            log::error!("the device was lost ({reason:?}): {message}");
+            flag.store(true, Ordering::Release);
+        });
+
+        lost
+    }
+
+    /// Renders all world content onto the target.
+    ///
+    /// Frames which can't be acquired are skipped (reconfiguring the target if needed), only
+    /// failing if rendering can't continue at all.
     pub fn render(
         &mut self,
         camera: &Camera,
         ui_context: &egui::Context,
         ui: egui::FullOutput,
         pre_present: impl FnOnce(),
-    ) {
-        let output = self.surface.get_current_texture().unwrap();
-        let view = output
-            .texture
-            .create_view(&TextureViewDescriptor::default());
+    ) -> anyhow::Result<()> {
+        if self.device_lost.load(Ordering::Acquire) {
+            self.recover_device()?;
+        }
+
+        let frame = match self.target.acquire() {
+            Ok(frame) => frame,
+            Err(e) => {
+                // the UI still expects its textures to be updated, even without drawing
+                self.update_ui_textures(&ui.textures_delta.set);
+                self.free_ui_textures(&ui.textures_delta.free);
+
+                return self.handle_surface_error(e);
+            }
+        };
 
         let mut encoder = self
             .device
@@ -123,55 +482,844 @@ impl Renderer {
             bytemuck::bytes_of(&camera.view_projection()),
         );
 
-        {
-            let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
-                label: Some("Renderer::main_render_pass"),
-                color_attachments: &[Some(RenderPassColorAttachment {
-                    view: &view,
-                    depth_slice: None,
-                    resolve_target: None,
-                    ops: Operations {
-                        load: LoadOp::Clear(Color {
-                            r: 0.01,
-                            g: 0.01,
-                            b: 0.01,
-                            a: 1.0,
-                        }),
-                        store: StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                timestamp_writes: None,
-                occlusion_query_set: None,
+        self.hdr_target.write_settings(
+            &self.queue,
+            self.tonemap_settings,
+            self.surface_config.format,
+        );
+        self.post
+            .write_settings(&self.queue, &self.post_settings, self.surface_config.format);
+
+        for (mesh, _) in &mut self.meshes {
+            mesh.write_instances(&self.device, &self.queue);
+        }
+
+        for emitter in &mut self.emitters {
+            emitter.write_uniform(&self.queue, camera);
+        }
+
+        let ui_frame = self.prepare_ui(&mut encoder, ui_context, ui);
+
+        let capture = self.take_capture_request();
+        let dump = std::mem::take(&mut self.graph_dump_requested);
+
+        let mut transient_pool = std::mem::take(&mut self.transient_pool);
+        let compute_dispatches = std::mem::take(&mut self.compute_dispatches);
+        let mut pending_capture = None;
+
+        let mut graph = RenderGraph::new();
+        let output = graph.import_texture("frame", &frame.view);
+
+        if !compute_dispatches.is_empty() {
+            graph.add_pass("compute", |pass| {
+                pass.side_effects();
+
+                |ctx| {
+                    let mut pass = ctx.encoder.begin_compute_pass(&ComputePassDescriptor {
+                        label: Some("Renderer::compute_pass"),
+                        timestamp_writes: None,
+                    });
+
+                    for dispatch in &compute_dispatches {
+                        dispatch.record(&mut pass);
+                    }
+                }
             });
+        }
+
+        self.add_passes(&mut graph, output, ui_frame.as_ref());
 
-            pass.set_bind_group(0, &self.camera_bind_group, &[]);
-            pass.set_pipeline(&self.pipelines.triangle_pipeline);
+        if capture {
+            graph.add_pass("capture", |pass| {
+                pass.read(output).side_effects();
 
-            pass.draw(0..3, 0..1);
+                |ctx| {
+                    pending_capture = Some(PendingCapture::record(
+                        ctx.device,
+                        ctx.encoder,
+                        &frame.texture,
+                    ));
+                }
+            });
         }
 
-        self.render_ui(&view, &mut encoder, ui_context, ui);
+        let graph_dump = dump.then(|| graph.to_dot());
+
+        graph.execute(&self.device, &mut encoder, &mut transient_pool)?;
+
+        self.transient_pool = transient_pool;
+        self.last_graph_dump = graph_dump.or(self.last_graph_dump.take());
+
+        if let Some(ui_frame) = ui_frame {
+            self.free_ui_textures(&ui_frame.free);
+        }
 
         self.queue.submit([encoder.finish()]);
 
+        if let Some(pending_capture) = pending_capture {
+            match pending_capture.read(&self.device) {
+                Ok(capture) => self.last_capture = Some(capture),
+                Err(e) => log::error!("failed to capture frame: {e:#}"),
+            }
+        }
+
         pre_present();
-        output.present();
+        frame.present();
+
+        Ok(())
+    }
+
+    /// Adds every pass drawing a frame onto the given output: the particle simulation, the main
+    /// render pass drawing every mesh and particle into the HDR target, post-processing and the
+    /// UI.
+    fn add_passes<'a>(
+        &'a self,
+        graph: &mut RenderGraph<'a>,
+        output: TextureHandle,
+        ui_frame: Option<&'a UiFrame>,
+    ) {
+        let camera = graph.import_buffer("camera", &self.camera_buffer);
+        let depth = graph.import_texture("depth", &self.depth_texture.view);
+        let hdr = graph.import_texture("hdr", &self.hdr_target.view);
+
+        let particles = self
+            .emitters
+            .iter()
+            .enumerate()
+            .map(|(i, emitter)| {
+                graph.import_buffer(&format!("particles({i})"), emitter.particle_buffer())
+            })
+            .collect::<Vec<_>>();
+
+        if !self.emitters.is_empty() {
+            graph.add_pass("particles", |pass| {
+                for &buffer in &particles {
+                    pass.write(buffer);
+                }
+
+                move |ctx| {
+                    let mut pass = ctx.encoder.begin_compute_pass(&ComputePassDescriptor {
+                        label: Some("Renderer::particle_simulation_pass"),
+                        timestamp_writes: None,
+                    });
+
+                    for emitter in &self.emitters {
+                        emitter.simulate(&mut pass, &self.pipelines);
+                    }
+                }
+            });
+        }
+
+        graph.add_pass("main", |pass| {
+            pass.read(camera).write(depth).write(hdr);
+
+            for &buffer in &particles {
+                pass.read(buffer);
+            }
+
+            move |ctx| self.main_render_pass(ctx.encoder, ctx.texture(hdr))
+        });
+
+        self.post.add_passes(
+            graph,
+            &self.pipelines,
+            &self.hdr_target,
+            hdr,
+            &self.post_settings,
+            output,
+        );
+
+        if let Some(ui_frame) = ui_frame {
+            graph.add_pass("ui", |pass| {
+                pass.write(output);
+
+                move |ctx| self.render_ui(ctx.encoder, ctx.texture(output), ui_frame)
+            });
+        }
+    }
+
+    /// Draws every instance of every mesh and then every particle into the given HDR view
+    /// (through the multisampled texture, if needed).
+    fn main_render_pass(&self, encoder: &mut CommandEncoder, hdr: &TextureView) {
+        let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Renderer::main_render_pass"),
+            color_attachments: &[Some(MsaaTexture::attachment(
+                self.msaa_texture.as_ref(),
+                hdr,
+                Color {
+                    r: 0.01,
+                    g: 0.01,
+                    b: 0.01,
+                    a: 1.0,
+                },
+            ))],
+            depth_stencil_attachment: Some(self.depth_texture.attachment()),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        pass.set_bind_group(0, &self.camera_bind_group, &[]);
+        pass.set_pipeline(&self.pipelines.mesh_pipeline);
+
+        for (mesh, material) in &self.meshes {
+            pass.set_bind_group(1, &self.materials[material.0].bind_group, &[]);
+            mesh.draw(&mut pass, 0..mesh.instance_count());
+        }
+
+        // particles are blended onto the meshes, so they have to be drawn last
+        if !self.emitters.is_empty() {
+            pass.set_pipeline(&self.pipelines.particle_pipeline);
+
+            for emitter in &self.emitters {
+                emitter.draw(&mut pass);
+            }
+        }
+    }
+
+    /// Recovers from a failure to acquire a frame, failing if the error is unrecoverable.
+    fn handle_surface_error(&mut self, error: SurfaceError) -> anyhow::Result<()> {
+        match error {
+            SurfaceError::Timeout => {
+                self.surface_errors.timeout += 1;
+                log::warn!("skipping frame: {error}");
+            }
+            SurfaceError::Outdated | SurfaceError::Lost => {
+                match error {
+                    SurfaceError::Outdated => self.surface_errors.outdated += 1,
+                    _ => self.surface_errors.lost += 1,
+                }
+
+                log::warn!("reconfiguring surface: {error}");
+                self.target.configure(&self.device, &self.surface_config);
+            }
+            SurfaceError::OutOfMemory => bail!("failed to acquire frame: {error}"),
+            SurfaceError::Other => {
+                self.surface_errors.other += 1;
+                log::warn!("skipping frame: {error}");
+            }
+        }
+
+        Ok(())
     }
 
-    /// Resizes the internal rendering surface to match the new target size.
+    /// Replaces a lost device with a new one, recreating every GPU resource and re-uploading all
+    /// meshes, materials, textures and UI textures.
+    ///
+    /// Particle emitters keep their settings, but lose every particle alive.
+    ///
+    /// Recreating the device needs to block, which isn't possible on the web.
+    fn recover_device(&mut self) -> anyhow::Result<()> {
+        if cfg!(target_arch = "wasm32") {
+            bail!("the device was lost");
+        }
+
+        log::warn!("recreating the lost device");
+
+        let compatible_surface = match &self.target {
+            RenderTarget::Window { surface, .. } => Some(surface),
+            RenderTarget::Offscreen { .. } => None,
+        };
+
+        let adapter = pollster::block_on(
+            self.config
+                .request_adapter(&self.instance, compatible_surface),
+        )
+        .context("failed to replace the lost device")?;
+
+        let (device, queue) =
+            pollster::block_on(adapter.request_device(&self.config.device_descriptor()))
+                .context("failed to replace the lost device")?;
+
+        self.adapter_info = adapter.get_info();
+        self.device_lost = Self::watch_device_lost(&device);
+        self.device = device;
+        self.queue = queue;
+
+        self.target.configure(&self.device, &self.surface_config);
+
+        let mut formats = self.pipelines.formats();
+
+        // the replacement adapter may not support the same sample counts
+        self.sample_counts =
+            MsaaTexture::supported_sample_counts(&adapter, self.device.features(), formats);
+
+        if !self.sample_counts.contains(&formats.sample_count) {
+            log::warn!("{}x MSAA is no longer supported", formats.sample_count);
+            formats.sample_count = 1;
+        }
+
+        self.shaders = Shaders::new(&self.device);
+        self.pipelines = Pipelines::new(&self.device, &self.shaders, formats);
+        self.samplers = SamplerCache::default();
+        self.transient_pool = TransientPool::default();
+
+        // the queued dispatches refer to resources of the lost device
+        self.compute_dispatches.clear();
+
+        self.color_lut = match &self.sources.color_lut {
+            Some(strip) => ColorLut::from_strip(&self.device, &self.queue, strip)
+                .expect("the color lookup table was valid when first uploaded"),
+            None => ColorLut::identity(&self.device, &self.queue),
+        };
+
+        self.recreate_frame_textures();
+
+        self.ui_renderer = egui_wgpu::Renderer::new(
+            &self.device,
+            self.surface_config.format,
+            egui_wgpu::RendererOptions::default(),
+        );
+
+        (self.camera_buffer, self.camera_bind_group) =
+            Self::create_camera_buffer(&self.device, &self.pipelines);
+
+        self.reupload_resources();
+
+        self.emitters = std::mem::take(&mut self.emitters)
+            .into_iter()
+            .map(|emitter| {
+                ParticleEmitter::new(
+                    &self.device,
+                    &self.pipelines,
+                    emitter.settings,
+                    emitter.capacity(),
+                )
+            })
+            .collect();
+
+        // shaders edited on disk since startup should stay in effect
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(watcher) = &self.shader_watcher {
+            let files = ShaderFiles::Directory(watcher.directory.clone());
+
+            for kind in ShaderKind::ALL {
+                match self.reload_shader(kind, &files) {
+                    Ok(()) => {
+                        self.shader_errors.remove(&kind);
+                    }
+                    Err(e) => {
+                        log::error!("failed to reload shader {}:\n{e}", kind.file_name());
+                        self.shader_errors.insert(kind, e);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Uploads every recorded resource onto the current device, replacing their lost copies
+    /// (and keeping their ids).
+    fn reupload_resources(&mut self) {
+        let sources = std::mem::take(&mut self.sources);
+
+        self.textures.clear();
+
+        for (i, source) in sources.textures.iter().enumerate() {
+            let label = format!("Texture({i})");
+
+            let texture = match source {
+                TextureSource::Image { image, color_space } => ImageTexture::from_rgba8(
+                    &self.device,
+                    &self.queue,
+                    &self.pipelines,
+                    &label,
+                    image,
+                    *color_space,
+                ),
+                TextureSource::File { path, color_space } => ImageTexture::load(
+                    &self.device,
+                    &self.queue,
+                    &self.pipelines,
+                    path,
+                    *color_space,
+                )
+                .unwrap_or_else(|e| {
+                    log::error!("{e:#}, replacing it with a white texture");
+
+                    let white = RgbaImage::from_pixel(1, 1, Rgba([255; 4]));
+                    ImageTexture::from_rgba8(
+                        &self.device,
+                        &self.queue,
+                        &self.pipelines,
+                        &label,
+                        &white,
+                        ColorSpace::Srgb,
+                    )
+                }),
+            };
+
+            self.textures.push(texture);
+        }
+
+        self.materials.clear();
+
+        for (data, textures) in &sources.materials {
+            self.create_material(data, textures);
+        }
+
+        // meshes keep the draw ranges and instances they were given since being uploaded
+        let previous = std::mem::take(&mut self.meshes);
+
+        for ((data, material), (mesh, _)) in sources.meshes.iter().zip(previous) {
+            let id = self.create_mesh(data, *material);
+            let recreated = &mut self.meshes[id.0].0;
+
+            recreated.draw_range = mesh.draw_range;
+            recreated.instances = mesh.instances;
+        }
+
+        for (id, image_delta) in &sources.ui_textures {
+            self.ui_renderer
+                .update_texture(&self.device, &self.queue, *id, image_delta);
+        }
+
+        self.sources = sources;
+    }
+
+    /// Returns the configuration the renderer was created with.
+    pub fn config(&self) -> &RendererConfig {
+        &self.config
+    }
+
+    /// Returns the info of the adapter being rendered with.
+    pub fn adapter_info(&self) -> &AdapterInfo {
+        &self.adapter_info
+    }
+
+    /// Returns how often acquiring a frame has failed so far.
+    pub fn surface_errors(&self) -> SurfaceErrorCounts {
+        self.surface_errors
+    }
+
+    /// Uploads the given mesh to the GPU, drawing it with the given material every frame from now
+    /// on.
+    ///
+    /// Meshes are drawn once by default, which can be changed through their
+    /// [`instances`](Mesh::instances).
+    pub fn upload_mesh(&mut self, data: &MeshData, material: MaterialId) -> MeshId {
+        self.sources.meshes.push((data.clone(), material));
+        self.create_mesh(data, material)
+    }
+
+    /// Uploads a mesh without recording it in the `sources`.
+    fn create_mesh(&mut self, data: &MeshData, material: MaterialId) -> MeshId {
+        let id = MeshId(self.meshes.len());
+        let mesh = Mesh::from_data(&self.device, &format!("Mesh({})", id.0), data);
+
+        self.meshes.push((mesh, material));
+
+        id
+    }
+
+    /// Returns a mutable reference to a previously uploaded mesh, whose instances are uploaded
+    /// again every frame.
+    pub fn mesh_mut(&mut self, id: MeshId) -> &mut Mesh {
+        &mut self.meshes[id.0].0
+    }
+
+    /// Uploads the given image to the GPU (generating its mipmaps), to be used by materials
+    /// uploaded afterwards.
+    pub fn upload_texture(&mut self, image: &RgbaImage, color_space: ColorSpace) -> TextureId {
+        let id = TextureId(self.textures.len());
+
+        self.textures.push(ImageTexture::from_rgba8(
+            &self.device,
+            &self.queue,
+            &self.pipelines,
+            &format!("Texture({})", id.0),
+            image,
+            color_space,
+        ));
+
+        self.sources.textures.push(TextureSource::Image {
+            image: image.clone(),
+            color_space,
+        });
+
+        id
+    }
+
+    /// Loads and uploads an image from disk (PNG, JPEG or HDR), to be used by materials uploaded
+    /// afterwards.
+    pub fn load_texture(
+        &mut self,
+        path: impl AsRef<Path>,
+        color_space: ColorSpace,
+    ) -> anyhow::Result<TextureId> {
+        let path = path.as_ref();
+
+        let texture = ImageTexture::load(
+            &self.device,
+            &self.queue,
+            &self.pipelines,
+            path,
+            color_space,
+        )?;
+
+        self.textures.push(texture);
+        self.sources.textures.push(TextureSource::File {
+            path: path.to_owned(),
+            color_space,
+        });
+
+        Ok(TextureId(self.textures.len() - 1))
+    }
+
+    /// Uploads the given material to the GPU, to be used by meshes uploaded afterwards.
+    ///
+    /// The texture indices of the material refer to the given list of textures, with missing
+    /// textures (including indices past the end of the list) replaced by [`TextureId::WHITE`].
+    pub fn upload_material(&mut self, data: &MaterialData, textures: &[TextureId]) -> MaterialId {
+        self.sources
+            .materials
+            .push((data.clone(), textures.to_vec()));
+
+        self.create_material(data, textures)
+    }
+
+    /// Uploads a material without recording it in the `sources`.
+    fn create_material(&mut self, data: &MaterialData, textures: &[TextureId]) -> MaterialId {
+        let view = |index: Option<usize>| {
+            let id = index
+                .and_then(|i| textures.get(i).copied())
+                .unwrap_or(TextureId::WHITE);
+            &self.textures[id.0].view
+        };
+
+        let material = Material::new(
+            &self.device,
+            &self.pipelines.material_bind_group_layout,
+            data,
+            MaterialTextures {
+                base_color: view(data.base_color_texture),
+                emissive: view(data.emissive_texture),
+                sampler: &self.samplers.material_sampler(&self.device),
+            },
+        );
+
+        self.materials.push(material);
+
+        MaterialId(self.materials.len() - 1)
+    }
+
+    /// Uploads all meshes, materials and textures of a model, returning the ids of its meshes.
+    pub fn upload_model(&mut self, model: &Model) -> Vec<MeshId> {
+        let textures = (0..model.textures.len())
+            .map(|i| self.upload_texture(&model.textures[i], model.texture_color_space(i)))
+            .collect::<Vec<_>>();
+
+        let materials = model
+            .materials
+            .iter()
+            .map(|material| self.upload_material(material, &textures))
+            .collect::<Vec<_>>();
+
+        model
+            .meshes
+            .iter()
+            .map(|mesh| {
+                let material = mesh.material.map_or(MaterialId::DEFAULT, |i| materials[i]);
+                self.upload_mesh(&mesh.data, material)
+            })
+            .collect()
+    }
+
+    /// Adds a particle emitter holding up to the given number of particles, simulated and drawn
+    /// every frame from now on.
+    pub fn add_emitter(&mut self, settings: EmitterSettings, capacity: u32) -> EmitterId {
+        self.emitters.push(ParticleEmitter::new(
+            &self.device,
+            &self.pipelines,
+            settings,
+            capacity,
+        ));
+
+        EmitterId(self.emitters.len() - 1)
+    }
+
+    /// Returns a mutable reference to a previously added particle emitter.
+    pub fn emitter_mut(&mut self, id: EmitterId) -> &mut ParticleEmitter {
+        &mut self.emitters[id.0]
+    }
+
+    /// Returns every particle emitter, in the order they were added.
+    pub fn emitters_mut(&mut self) -> impl Iterator<Item = &mut ParticleEmitter> {
+        self.emitters.iter_mut()
+    }
+
+    /// Advances the time simulated by every particle emitter during the next rendered frame.
+    pub fn update_particles(&mut self, dt: f32) {
+        for emitter in &mut self.emitters {
+            emitter.advance(dt);
+        }
+    }
+
+    /// Reads every particle of an emitter back from the GPU, waiting for all rendered frames to
+    /// complete.
+    pub fn read_particles(&self, id: EmitterId) -> anyhow::Result<Vec<Particle>> {
+        compute::read_buffer(
+            &self.device,
+            &self.queue,
+            self.emitters[id.0].particle_buffer(),
+        )
+    }
+
+    /// Queues a compute dispatch to run during the next rendered frame, before the main render
+    /// pass (and after any previously queued dispatch).
+    pub fn dispatch_compute(&mut self, dispatch: ComputeDispatch) {
+        self.compute_dispatches.push(dispatch);
+    }
+
+    /// Replaces the lookup table used for color grading with one stored as a strip of squares
+    /// (see [`ColorLut::from_strip`]), failing if the image isn't laid out as such.
+    pub fn set_color_lut(&mut self, strip: &RgbaImage) -> anyhow::Result<()> {
+        self.color_lut = ColorLut::from_strip(&self.device, &self.queue, strip)?;
+        self.sources.color_lut = Some(strip.clone());
+
+        // the post-processing bind groups refer to the previous lookup table
+        self.recreate_frame_textures();
+
+        Ok(())
+    }
+
+    /// Loads the lookup table used for color grading from an image on disk, see
+    /// [`Renderer::set_color_lut`].
+    pub fn load_color_lut(&mut self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+
+        let strip = image::open(path)
+            .with_context(|| format!("failed to load color lookup table {}", path.display()))?;
+
+        self.set_color_lut(&strip.into_rgba8())
+    }
+
+    /// Requests that the next rendered frame be captured, to be retrieved with
+    /// [`Renderer::take_capture`].
+    pub fn request_capture(&mut self) {
+        self.capture_requested = true;
+    }
+
+    /// Takes the most recently captured frame, if there is one.
+    pub fn take_capture(&mut self) -> Option<FrameCapture> {
+        self.last_capture.take()
+    }
+
+    /// Consumes the pending capture request, returning whether this frame should be captured.
+    fn take_capture_request(&mut self) -> bool {
+        if !std::mem::take(&mut self.capture_requested) {
+            return false;
+        }
+
+        if !self.surface_config.usage.contains(TextureUsages::COPY_SRC) {
+            log::warn!("the render target does not support being copied, skipping capture");
+            return false;
+        }
+
+        true
+    }
+
+    /// Requests that the render graph of the next rendered frame be dumped, to be retrieved with
+    /// [`Renderer::take_graph_dump`].
+    pub fn request_graph_dump(&mut self) {
+        self.graph_dump_requested = true;
+    }
+
+    /// Takes the most recently dumped render graph (in the Graphviz DOT language), if there is
+    /// one.
+    pub fn take_graph_dump(&mut self) -> Option<String> {
+        self.last_graph_dump.take()
+    }
+
+    /// Starts watching the given directory for changes to shader sources, which are reloaded by
+    /// [`Renderer::reload_changed_shaders`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn watch_shaders(&mut self, directory: impl AsRef<Path>) -> anyhow::Result<()> {
+        self.shader_watcher = Some(hot_reload::ShaderWatcher::new(directory.as_ref())?);
+        Ok(())
+    }
+
+    /// Recompiles every watched shader which has changed on disk, rebuilding the pipelines using
+    /// them.
+    ///
+    /// Shaders which fail to compile keep their previous pipelines, with their errors available
+    /// from [`Renderer::shader_errors`] until a reload of the same shader succeeds.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn reload_changed_shaders(&mut self) {
+        let Some(watcher) = &self.shader_watcher else {
+            return;
+        };
+
+        let files = ShaderFiles::Directory(watcher.directory.clone());
+
+        for kind in watcher.changed_shaders() {
+            match self.reload_shader(kind, &files) {
+                Ok(()) => {
+                    log::info!("reloaded shader {}", kind.file_name());
+                    self.shader_errors.remove(&kind);
+                }
+                Err(e) => {
+                    log::error!("failed to reload shader {}:\n{e}", kind.file_name());
+                    self.shader_errors.insert(kind, e);
+                }
+            }
+        }
+    }
+
+    /// Recompiles a shader from the given files along with every pipeline using it, leaving the
+    /// current shaders and pipelines untouched if either fails.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn reload_shader(&mut self, kind: ShaderKind, files: &ShaderFiles) -> Result<(), ShaderError> {
+        let mut shaders = self.shaders.clone();
+        let mut pipelines = self.pipelines.clone();
+
+        // naga validation catches most errors, but pipelines may still not match their layouts
+        self.device.push_error_scope(ErrorFilter::Validation);
+
+        let reloaded = shaders
+            .reload(&self.device, kind, files)
+            .and_then(|()| Self::check_bind_groups(&self.shaders, &shaders, kind))
+            .and_then(|()| pipelines.rebuild(&self.device, &shaders, kind));
+
+        let error = pollster::block_on(self.device.pop_error_scope());
+
+        reloaded?;
+
+        if let Some(error) = error {
+            return Err(ShaderError {
+                kind,
+                message: error.to_string(),
+            });
+        }
+
+        self.shaders = shaders;
+        self.pipelines = pipelines;
+
+        Ok(())
+    }
+
+    /// Checks that a reloaded shader still declares the same bind groups, which existing bind
+    /// groups (such as materials) were created for.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn check_bind_groups(
+        previous: &Shaders,
+        reloaded: &Shaders,
+        kind: ShaderKind,
+    ) -> Result<(), ShaderError> {
+        if previous.reflection(kind).bind_groups == reloaded.reflection(kind).bind_groups {
+            return Ok(());
+        }
+
+        Err(ShaderError {
+            kind,
+            message: "the bind groups of the shader have changed, which requires a restart"
+                .to_owned(),
+        })
+    }
+
+    /// Returns the error of every shader whose most recent reload failed, in the order of
+    /// [`ShaderKind::ALL`].
+    pub fn shader_errors(&self) -> impl Iterator<Item = &ShaderError> {
+        ShaderKind::ALL
+            .into_iter()
+            .filter_map(|kind| self.shader_errors.get(&kind))
+    }
+
+    /// Resizes the internal rendering target to match the new target size.
+    ///
+    /// Targets can't have a zero dimension, so such sizes (such as minimized windows) are ignored
+    /// and nothing should be rendered until the next valid resize.
     pub fn resize(&mut self, size: PhysicalSize<u32>) {
         let PhysicalSize { width, height } = size;
 
+        if width == 0 || height == 0 {
+            return;
+        }
+
         self.surface_config.width = width;
         self.surface_config.height = height;
 
-        self.surface.configure(&self.device, &self.surface_config);
+        self.target.configure(&self.device, &self.surface_config);
+        self.recreate_frame_textures();
     }
 
-    /// Returns an appropriate default [`SurfaceConfiguration`] for rendering to the given window.
-    fn get_surface_config(window: &Window) -> SurfaceConfiguration {
-        let PhysicalSize { width, height } = window.inner_size();
+    /// Changes the format of the depth texture, rebuilding all pipelines which depend on it.
+    ///
+    /// Fails without changing anything if the format isn't a depth format, or needs features the
+    /// device lacks.
+    pub fn set_depth_format(&mut self, format: TextureFormat) -> anyhow::Result<()> {
+        if !format.is_depth_stencil_format() {
+            bail!("{format:?} is not a depth format");
+        }
+
+        let missing = format.required_features() - self.device.features();
+
+        if !missing.is_empty() {
+            bail!("{format:?} needs features the device lacks: {missing:?}");
+        }
+
+        let formats = TargetFormats {
+            depth: format,
+            ..self.pipelines.formats()
+        };
+
+        self.pipelines
+            .set_formats(&self.device, &self.shaders, formats);
+
+        self.recreate_frame_textures();
+
+        Ok(())
+    }
+
+    /// Returns the number of samples taken per pixel by the main render pass.
+    pub fn sample_count(&self) -> u32 {
+        self.pipelines.formats().sample_count
+    }
+
+    /// Returns every sample count supported for the formats of the target.
+    pub fn sample_counts(&self) -> &[u32] {
+        &self.sample_counts
+    }
+
+    /// Changes the number of samples taken per pixel (1 disabling MSAA), rebuilding all
+    /// pipelines which depend on it and ignoring unsupported counts.
+    pub fn set_sample_count(&mut self, sample_count: u32) {
+        if !self.sample_counts.contains(&sample_count) {
+            log::warn!("{sample_count}x MSAA is not supported by the device");
+            return;
+        }
+
+        let formats = TargetFormats {
+            sample_count,
+            ..self.pipelines.formats()
+        };
+
+        self.pipelines
+            .set_formats(&self.device, &self.shaders, formats);
+
+        self.recreate_frame_textures();
+    }
+
+    /// Returns every present mode supported by the target.
+    pub fn present_modes(&self) -> &[PresentMode] {
+        &self.present_modes
+    }
+
+    /// Changes how frames are presented to the target, ignoring unsupported modes.
+    pub fn set_present_mode(&mut self, mode: PresentMode) {
+        if !self.present_modes.contains(&mode) {
+            log::warn!("present mode {mode:?} is not supported by the target");
+            return;
+        }
+
+        self.surface_config.present_mode = mode;
+        self.target.configure(&self.device, &self.surface_config);
+    }
+
+    /// Returns an appropriate default [`SurfaceConfiguration`] for rendering a target of the given
+    /// size and format.
+    fn get_surface_config(size: PhysicalSize<u32>, format: TextureFormat) -> SurfaceConfiguration {
+        let PhysicalSize { width, height } = size;
 
         let width = width.max(1);
         let height = height.max(1);
@@ -180,7 +1328,7 @@ impl Renderer {
             width,
             height,
             usage: TextureUsages::RENDER_ATTACHMENT,
-            format: TextureFormat::Bgra8Unorm,
+            format,
             present_mode: PresentMode::AutoVsync,
             desired_maximum_frame_latency: 1,
             alpha_mode: CompositeAlphaMode::Auto,
@@ -188,23 +1336,26 @@ impl Renderer {
         }
     }
 
-    fn render_ui(
+    /// Tessellates the output of the UI and uploads its textures and buffers, returning what
+    /// remains to be drawn by the UI pass.
+    fn prepare_ui(
         &mut self,
-        view: &TextureView,
         encoder: &mut CommandEncoder,
         context: &egui::Context,
         output: egui::FullOutput,
-    ) {
+    ) -> Option<UiFrame> {
+        // headless renderers may never run the UI, in which case there are no fonts to tessellate
+        if output.shapes.is_empty() && output.textures_delta.is_empty() {
+            return None;
+        }
+
         let tris = context.tessellate(output.shapes, output.pixels_per_point);
 
-        for (id, image_delta) in &output.textures_delta.set {
-            self.ui_renderer
-                .update_texture(&self.device, &self.queue, *id, &image_delta);
-        }
+        self.update_ui_textures(&output.textures_delta.set);
 
         let screen_descriptor = egui_wgpu::ScreenDescriptor {
-            size_in_pixels: self.window.inner_size().into(),
-            pixels_per_point: self.window.scale_factor() as _,
+            size_in_pixels: [self.surface_config.width, self.surface_config.height],
+            pixels_per_point: self.target.scale_factor(),
         };
 
         self.ui_renderer.update_buffers(
@@ -215,6 +1366,15 @@ impl Renderer {
             &screen_descriptor,
         );
 
+        Some(UiFrame {
+            tris,
+            screen_descriptor,
+            free: output.textures_delta.free,
+        })
+    }
+
+    /// Renders a prepared UI on top of the given view.
+    fn render_ui(&self, encoder: &mut CommandEncoder, view: &TextureView, ui_frame: &UiFrame) {
         let mut pass = encoder
             .begin_render_pass(&wgpu::RenderPassDescriptor {
                 color_attachments: &[Some(RenderPassColorAttachment {
@@ -234,12 +1394,23 @@ impl Renderer {
             .forget_lifetime();
 
         self.ui_renderer
-            .render(&mut pass, &tris, &screen_descriptor);
+            .render(&mut pass, &ui_frame.tris, &ui_frame.screen_descriptor);
+    }
 
-        drop(pass);
+    /// Uploads all new or changed UI textures.
+    fn update_ui_textures(&mut self, set: &[(egui::TextureId, egui::epaint::ImageDelta)]) {
+        for (id, image_delta) in set {
+            self.ui_renderer
+                .update_texture(&self.device, &self.queue, *id, image_delta);
+            self.sources.update_ui_texture(*id, image_delta);
+        }
+    }
 
-        for x in &output.textures_delta.free {
-            self.ui_renderer.free_texture(x)
+    /// Frees all UI textures which are no longer used.
+    fn free_ui_textures(&mut self, free: &[egui::TextureId]) {
+        for x in free {
+            self.ui_renderer.free_texture(x);
+            self.sources.free_ui_texture(*x);
         }
     }
 }
@@ -0,0 +1,206 @@
+use std::path::Path;
+
+use ::gltf::{
+    Document, Node, Primitive, buffer,
+    image::{Data as ImageData, Format},
+    mesh::Mode,
+};
+use anyhow::{Context, bail};
+use glam::{Mat3, Mat4, Vec2, Vec3, Vec4};
+use image::RgbaImage;
+
+use crate::renderer::{
+    material::MaterialData,
+    mesh::{MeshData, Vertex},
+    model::{Model, ModelMesh},
+};
+
+/// Loads a glTF 2.0 model (either `.gltf` or `.glb`), along with any embedded or external
+/// buffers and textures.
+///
+/// The node hierarchy of the default scene is flattened, with each node's transform applied to
+/// the vertices of its meshes.
+pub fn load(path: &Path) -> anyhow::Result<Model> {
+    let (document, buffers, images) = ::gltf::import(path)
+        .with_context(|| format!("failed to import glTF model {}", path.display()))?;
+
+    let textures = document
+        .textures()
+        .map(|texture| convert_image(&images[texture.source().index()]))
+        .collect::<anyhow::Result<_>>()?;
+
+    let materials = document.materials().map(convert_material).collect();
+
+    let mut model = Model {
+        meshes: Vec::new(),
+        materials,
+        textures,
+    };
+
+    for node in root_nodes(&document) {
+        load_node(&mut model, &buffers, &node, Mat4::IDENTITY)?;
+    }
+
+    Ok(model)
+}
+
+/// Returns the root nodes of the default scene (or the first scene if there is no default).
+fn root_nodes(document: &Document) -> Vec<Node<'_>> {
+    document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+        .map(|scene| scene.nodes().collect())
+        .unwrap_or_default()
+}
+
+/// Loads all meshes of a node and its children, given the transform of its parent.
+fn load_node(
+    model: &mut Model,
+    buffers: &[buffer::Data],
+    node: &Node,
+    parent_transform: Mat4,
+) -> anyhow::Result<()> {
+    let transform = parent_transform * Mat4::from_cols_array_2d(&node.transform().matrix());
+
+    if let Some(mesh) = node.mesh() {
+        for primitive in mesh.primitives() {
+            let Some(data) = load_primitive(buffers, &primitive, transform)? else {
+                log::warn!(
+                    "skipping primitive {} of mesh {:?}, only triangles are supported",
+                    primitive.index(),
+                    mesh.name()
+                );
+
+                continue;
+            };
+
+            model.meshes.push(ModelMesh {
+                name: mesh.name().map(str::to_owned),
+                data,
+                material: primitive.material().index(),
+            });
+        }
+    }
+
+    for child in node.children() {
+        load_node(model, buffers, &child, transform)?;
+    }
+
+    Ok(())
+}
+
+/// Reads the geometry of a primitive, transformed by the given matrix.
+///
+/// Returns `None` if the primitive is not made of triangles.
+fn load_primitive(
+    buffers: &[buffer::Data],
+    primitive: &Primitive,
+    transform: Mat4,
+) -> anyhow::Result<Option<MeshData>> {
+    if primitive.mode() != Mode::Triangles {
+        return Ok(None);
+    }
+
+    let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+    let normal_transform = Mat3::from_mat4(transform).inverse().transpose();
+
+    let positions = reader
+        .read_positions()
+        .context("primitive is missing vertex positions")?;
+
+    let mut normals = reader.read_normals();
+    let mut tex_coords = reader.read_tex_coords(0).map(|t| t.into_f32());
+    let mut colors = reader.read_colors(0).map(|c| c.into_rgb_f32());
+
+    let vertices = positions
+        .map(|position| Vertex {
+            position: transform.transform_point3(position.into()),
+            normal: normals
+                .as_mut()
+                .and_then(Iterator::next)
+                .map_or(Vec3::ZERO, |n| {
+                    (normal_transform * Vec3::from(n)).normalize_or_zero()
+                }),
+            tex_coords: tex_coords
+                .as_mut()
+                .and_then(Iterator::next)
+                .map_or(Vec2::ZERO, Vec2::from),
+            color: colors
+                .as_mut()
+                .and_then(Iterator::next)
+                .map_or(Vec3::ONE, Vec3::from),
+        })
+        .collect::<Vec<_>>();
+
+    let indices = match reader.read_indices() {
+        Some(indices) => indices.into_u32().collect(),
+        None => (0..vertices.len() as u32).collect(),
+    };
+
+    let mut data = MeshData { vertices, indices };
+
+    if normals.is_none() {
+        data.generate_flat_normals();
+    }
+
+    Ok(Some(data))
+}
+
+/// Converts a glTF material into [`MaterialData`].
+fn convert_material(material: ::gltf::Material) -> MaterialData {
+    let pbr = material.pbr_metallic_roughness();
+
+    MaterialData {
+        name: material.name().map(str::to_owned),
+        base_color_factor: Vec4::from(pbr.base_color_factor()),
+        metallic_factor: pbr.metallic_factor(),
+        roughness_factor: pbr.roughness_factor(),
+        emissive_factor: Vec3::from(material.emissive_factor()),
+        base_color_texture: pbr.base_color_texture().map(|t| t.texture().index()),
+        metallic_roughness_texture: pbr
+            .metallic_roughness_texture()
+            .map(|t| t.texture().index()),
+        normal_texture: material.normal_texture().map(|t| t.texture().index()),
+        emissive_texture: material.emissive_texture().map(|t| t.texture().index()),
+    }
+}
+
+/// Converts a decoded glTF image into an RGBA8 image.
+fn convert_image(data: &ImageData) -> anyhow::Result<RgbaImage> {
+    let channels = match data.format {
+        Format::R8 | Format::R16 => 1,
+        Format::R8G8 | Format::R16G16 => 2,
+        Format::R8G8B8 | Format::R16G16B16 => 3,
+        Format::R8G8B8A8 | Format::R16G16B16A16 => 4,
+        format => bail!("unsupported glTF image format {format:?}"),
+    };
+
+    let wide = matches!(
+        data.format,
+        Format::R16 | Format::R16G16 | Format::R16G16B16 | Format::R16G16B16A16
+    );
+
+    // keeps only the most significant byte of 16 bit channels
+    let bytes = match wide {
+        true => data
+            .pixels
+            .chunks_exact(2)
+            .map(|c| (u16::from_ne_bytes([c[0], c[1]]) >> 8) as u8)
+            .collect(),
+        false => data.pixels.clone(),
+    };
+
+    let pixels = bytes
+        .chunks_exact(channels)
+        .flat_map(|pixel| match *pixel {
+            [r] => [r, r, r, 255],
+            [r, g] => [r, g, 0, 255],
+            [r, g, b] => [r, g, b, 255],
+            [r, g, b, a] => [r, g, b, a],
+            _ => unreachable!(),
+        })
+        .collect();
+
+    RgbaImage::from_raw(data.width, data.height, pixels)
+        .context("glTF image data does not match its size")
+}
@@ -0,0 +1,62 @@
+pub mod gltf;
+pub mod obj;
+
+use std::path::Path;
+
+use anyhow::bail;
+use image::RgbaImage;
+
+use crate::renderer::{material::MaterialData, mesh::MeshData, texture::ColorSpace};
+
+/// A model loaded from disk, holding all of its meshes, materials and textures.
+#[derive(Debug, Clone, Default)]
+pub struct Model {
+    /// All meshes of the model, already transformed into the model's space.
+    pub meshes: Vec<ModelMesh>,
+    /// All materials referenced by the `meshes`.
+    pub materials: Vec<MaterialData>,
+    /// All textures referenced by the `materials`.
+    pub textures: Vec<RgbaImage>,
+}
+
+/// A single mesh within a [`Model`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelMesh {
+    /// The name of the mesh, if it has one.
+    pub name: Option<String>,
+    /// The geometry of the mesh.
+    pub data: MeshData,
+    /// The index of the mesh's material within the model, if it has one.
+    pub material: Option<usize>,
+}
+
+impl Model {
+    /// Loads a model from the given path, choosing the format from its extension.
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+
+        match extension.as_deref() {
+            Some("gltf" | "glb") => gltf::load(path),
+            Some("obj") => obj::load(path),
+            _ => bail!("unsupported model format: {}", path.display()),
+        }
+    }
+
+    /// Returns the color space of the texture at the given index, based on how the materials
+    /// use it (textures holding colors are sRGB, everything else is linear).
+    pub fn texture_color_space(&self, index: usize) -> ColorSpace {
+        let holds_color = self.materials.iter().any(|material| {
+            material.base_color_texture == Some(index) || material.emissive_texture == Some(index)
+        });
+
+        match holds_color {
+            true => ColorSpace::Srgb,
+            false => ColorSpace::Linear,
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::Context;
+use glam::{Vec2, Vec3, Vec4, vec2};
+use image::RgbaImage;
+
+use crate::renderer::{
+    material::MaterialData,
+    mesh::{MeshData, Vertex},
+    model::{Model, ModelMesh},
+};
+
+/// How normals are generated for meshes which don't specify their own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NormalGeneration {
+    /// Every face uses its own normal, giving a faceted look.
+    Flat,
+    /// Normals are averaged across the faces sharing a vertex.
+    #[default]
+    Smooth,
+}
+
+/// Loads a Wavefront OBJ model along with the materials and textures of any referenced MTL
+/// libraries, smoothing normals of meshes without any.
+pub fn load(path: &Path) -> anyhow::Result<Model> {
+    load_with(path, NormalGeneration::default())
+}
+
+/// Loads a Wavefront OBJ model, generating normals as given for meshes without any.
+///
+/// Missing MTL libraries and textures are logged and skipped, as OBJ files are commonly shared
+/// without them.
+pub fn load_with(path: &Path, normals: NormalGeneration) -> anyhow::Result<Model> {
+    let (models, materials) = tobj::load_obj(path, &tobj::GPU_LOAD_OPTIONS)
+        .with_context(|| format!("failed to load OBJ model {}", path.display()))?;
+
+    let materials = materials.unwrap_or_else(|e| {
+        log::warn!("failed to load materials of {}: {e}", path.display());
+        Vec::new()
+    });
+
+    let directory = path.parent().unwrap_or(Path::new(""));
+    let mut textures = TextureLoader::new(directory);
+
+    let materials = materials
+        .iter()
+        .map(|material| convert_material(material, &mut textures))
+        .collect();
+
+    let meshes = models
+        .into_iter()
+        .map(|model| ModelMesh {
+            name: Some(model.name),
+            material: model.mesh.material_id,
+            data: convert_mesh(&model.mesh, normals),
+        })
+        .collect();
+
+    Ok(Model {
+        meshes,
+        materials,
+        textures: textures.textures,
+    })
+}
+
+/// Converts a (triangulated and single indexed) OBJ mesh into [`MeshData`].
+fn convert_mesh(mesh: &tobj::Mesh, normals: NormalGeneration) -> MeshData {
+    let vertex_count = mesh.positions.len() / 3;
+
+    let vec3_at = |values: &[f32], i: usize| Vec3::from_slice(&values[i * 3..i * 3 + 3]);
+
+    let vertices = (0..vertex_count)
+        .map(|i| Vertex {
+            position: vec3_at(&mesh.positions, i),
+            normal: match mesh.normals.is_empty() {
+                true => Vec3::ZERO,
+                false => vec3_at(&mesh.normals, i),
+            },
+            // OBJ places the origin of textures in the bottom left corner
+            tex_coords: match mesh.texcoords.is_empty() {
+                true => Vec2::ZERO,
+                false => vec2(mesh.texcoords[i * 2], 1.0 - mesh.texcoords[i * 2 + 1]),
+            },
+            color: match mesh.vertex_color.is_empty() {
+                true => Vec3::ONE,
+                false => vec3_at(&mesh.vertex_color, i),
+            },
+        })
+        .collect();
+
+    let mut data = MeshData {
+        vertices,
+        indices: mesh.indices.clone(),
+    };
+
+    if mesh.normals.is_empty() {
+        match normals {
+            NormalGeneration::Flat => data.generate_flat_normals(),
+            NormalGeneration::Smooth => data.generate_smooth_normals(),
+        }
+    }
+
+    data
+}
+
+/// Converts an MTL material into [`MaterialData`], loading any of its textures.
+///
+/// Physically based parameters from the common MTL extension (`Pr`, `Pm` and `Ke`) are used when
+/// present, otherwise roughness is approximated from the specular exponent.
+fn convert_material(material: &tobj::Material, textures: &mut TextureLoader) -> MaterialData {
+    let parameter = |name: &str| {
+        material.unknown_param.get(name).map(|value| {
+            value
+                .split_whitespace()
+                .filter_map(|v| v.parse::<f32>().ok())
+        })
+    };
+
+    let diffuse = material.diffuse.map_or(Vec3::ONE, Vec3::from);
+    let alpha = material.dissolve.unwrap_or(1.0);
+
+    let roughness = parameter("Pr")
+        .and_then(|mut values| values.next())
+        .or_else(|| {
+            material
+                .shininess
+                .map(|shininess| (2.0 / (shininess.max(0.0) + 2.0)).sqrt())
+        })
+        .unwrap_or(1.0);
+
+    let emissive = parameter("Ke")
+        .map(|values| values.collect::<Vec<_>>())
+        .filter(|values| values.len() == 3)
+        .map_or(Vec3::ZERO, |values| Vec3::from_slice(&values));
+
+    MaterialData {
+        name: Some(material.name.clone()),
+        base_color_factor: Vec4::from((diffuse, alpha)),
+        metallic_factor: parameter("Pm")
+            .and_then(|mut values| values.next())
+            .unwrap_or(0.0),
+        roughness_factor: roughness,
+        emissive_factor: emissive,
+        base_color_texture: textures.load(material.diffuse_texture.as_deref()),
+        metallic_roughness_texture: None,
+        normal_texture: textures.load(material.normal_texture.as_deref()),
+        emissive_texture: textures.load(material.unknown_param.get("map_Ke").map(String::as_str)),
+    }
+}
+
+/// Loads the textures referenced by materials, only loading each file once.
+struct TextureLoader<'a> {
+    /// The directory texture paths are relative to.
+    directory: &'a Path,
+    /// The index of every texture loaded so far, by its path.
+    indices: HashMap<String, Option<usize>>,
+    /// All textures loaded so far.
+    textures: Vec<RgbaImage>,
+}
+
+impl<'a> TextureLoader<'a> {
+    /// Creates a new [`TextureLoader`] loading textures relative to the given directory.
+    fn new(directory: &'a Path) -> Self {
+        Self {
+            directory,
+            indices: HashMap::new(),
+            textures: Vec::new(),
+        }
+    }
+
+    /// Loads the texture with the given name, returning its index (or `None` if it fails).
+    fn load(&mut self, name: Option<&str>) -> Option<usize> {
+        let name = name?;
+
+        if let Some(index) = self.indices.get(name) {
+            return *index;
+        }
+
+        let path = self.directory.join(name);
+
+        let index = match image::open(&path) {
+            Ok(image) => {
+                self.textures.push(image.into_rgba8());
+                Some(self.textures.len() - 1)
+            }
+            Err(e) => {
+                log::warn!("failed to load texture {}: {e}", path.display());
+                None
+            }
+        };
+
+        self.indices.insert(name.to_owned(), index);
+
+        index
+    }
+}
@@ -0,0 +1,96 @@
+use wgpu::*;
+
+use crate::renderer::pipelines::TargetFormats;
+
+/// A multisampled color texture matching the size of the render target, which the main render
+/// pass draws into before resolving onto the target.
+pub struct MsaaTexture {
+    /// The underlying multisampled texture.
+    pub texture: Texture,
+    /// A view into the whole `texture`.
+    pub view: TextureView,
+}
+
+impl MsaaTexture {
+    /// Every sample count which may be chosen, of which the adapter supports a subset.
+    pub const SAMPLE_COUNTS: [u32; 4] = [1, 2, 4, 8];
+
+    /// Creates a new [`MsaaTexture`] of the given size, format and sample count.
+    pub fn new(
+        device: &Device,
+        format: TextureFormat,
+        width: u32,
+        height: u32,
+        sample_count: u32,
+    ) -> Self {
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("MsaaTexture::texture"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&TextureViewDescriptor::default());
+
+        Self { texture, view }
+    }
+
+    /// Returns the sample counts (of the [`MsaaTexture::SAMPLE_COUNTS`]) supported by both the
+    /// color and depth formats, on a device with the given features.
+    ///
+    /// Counts other than 1 and 4 are only available with
+    /// [`Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES`].
+    pub fn supported_sample_counts(
+        adapter: &Adapter,
+        features: Features,
+        formats: TargetFormats,
+    ) -> Vec<u32> {
+        let adapter_specific =
+            features.contains(Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES);
+
+        Self::SAMPLE_COUNTS
+            .into_iter()
+            .filter(|&count| matches!(count, 1 | 4) || adapter_specific)
+            .filter(|&count| {
+                [formats.color, formats.depth].into_iter().all(|format| {
+                    adapter
+                        .get_texture_format_features(format)
+                        .flags
+                        .sample_count_supported(count)
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the attachment clearing the multisampled texture and resolving it into the given
+    /// view, or clearing the view directly without a multisampled texture.
+    pub fn attachment<'a>(
+        msaa: Option<&'a Self>,
+        target: &'a TextureView,
+        clear_color: Color,
+    ) -> RenderPassColorAttachment<'a> {
+        let (view, resolve_target, store) = match msaa {
+            // only the resolved samples are needed after the pass
+            Some(msaa) => (&msaa.view, Some(target), StoreOp::Discard),
+            None => (target, None, StoreOp::Store),
+        };
+
+        RenderPassColorAttachment {
+            view,
+            depth_slice: None,
+            resolve_target,
+            ops: Operations {
+                load: LoadOp::Clear(clear_color),
+                store,
+            },
+        }
+    }
+}
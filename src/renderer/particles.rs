@@ -0,0 +1,261 @@
+use bytemuck::{Pod, Zeroable};
+use glam::{Vec3, Vec4, vec3, vec4};
+use wgpu::*;
+
+use crate::renderer::{camera::Camera, mesh::VertexLayout, pipelines::Pipelines};
+
+/// Identifies a particle emitter added to the renderer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EmitterId(pub(crate) usize);
+
+/// A single particle as stored on the GPU, simulated by a compute shader and drawn as an instance
+/// of a billboard.
+///
+/// Particles which are at least as old as their lifetime are dead, and aren't drawn.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Pod, Zeroable)]
+pub struct Particle {
+    /// The position of the particle in world space.
+    pub position: Vec3,
+    /// How long the particle has been alive (in seconds).
+    pub age: f32,
+    /// The velocity of the particle (in units per second).
+    pub velocity: Vec3,
+    /// How long the particle lives for (in seconds).
+    pub lifetime: f32,
+}
+
+/// Controls how an emitter spawns particles, and how they move and look over their lifetime.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EmitterSettings {
+    /// Where particles are spawned, in world space.
+    pub position: Vec3,
+    /// The direction particles are spawned towards.
+    pub direction: Vec3,
+    /// The angle between the `direction` and the edge of the cone particles are spawned within
+    /// (in radians).
+    pub cone_angle: f32,
+    /// The speed particles are spawned with (in units per second).
+    pub speed: f32,
+    /// How many particles are spawned per second.
+    pub spawn_rate: f32,
+    /// How long every particle lives for (in seconds).
+    pub lifetime: f32,
+    /// The acceleration applied to every particle (in units per second squared).
+    pub gravity: Vec3,
+    /// The (linear, possibly HDR) color of particles when spawned, with alpha.
+    pub start_color: Vec4,
+    /// The color particles fade into by the end of their lifetime.
+    pub end_color: Vec4,
+    /// The width and height of every particle's billboard (in units).
+    pub size: f32,
+}
+
+/// The layout of the emitter uniform buffer, matching `Emitter` in `particle.wgsl`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+struct EmitterUniform {
+    position: Vec3,
+    spawn_start: u32,
+    direction: Vec3,
+    spawn_count: u32,
+    gravity: Vec3,
+    cone_angle: f32,
+    start_color: Vec4,
+    end_color: Vec4,
+    camera_right: Vec3,
+    delta_time: f32,
+    camera_up: Vec3,
+    size: f32,
+    speed: f32,
+    lifetime: f32,
+    seed: u32,
+    capacity: u32,
+}
+
+/// An emitter whose particles are simulated and drawn entirely on the GPU.
+///
+/// Particles are spawned into a fixed size ring buffer, so once it is full the oldest particles
+/// are replaced by new ones.
+pub struct ParticleEmitter {
+    /// How the emitter spawns and draws particles, applied from the next frame on.
+    pub settings: EmitterSettings,
+    /// The maximum number of particles alive at once.
+    capacity: u32,
+    /// The storage buffer holding every particle, also read as an instance buffer.
+    particle_buffer: Buffer,
+    /// The uniform buffer holding the settings and the state of the current frame.
+    uniform_buffer: Buffer,
+    /// The bind group holding the `particle_buffer` and `uniform_buffer`, used by the simulation.
+    simulation_bind_group: BindGroup,
+    /// The bind group holding the `uniform_buffer`, used when drawing.
+    bind_group: BindGroup,
+
+    /// The time passed since the last simulated frame (in seconds).
+    pending_time: f32,
+    /// The fraction of a particle which should have been spawned, carried over between frames.
+    spawn_remainder: f32,
+    /// The index in the ring buffer the next particle is spawned at.
+    next_spawn: u32,
+    /// The number of frames simulated so far, seeding the random numbers of each frame.
+    frame: u32,
+}
+
+impl VertexLayout for Particle {
+    const ATTRIBUTES: &'static [VertexAttribute] = &vertex_attr_array![
+        0 => Float32x3,
+        1 => Float32,
+        2 => Float32x3,
+        3 => Float32,
+    ];
+}
+
+impl Default for EmitterSettings {
+    fn default() -> Self {
+        Self {
+            position: Vec3::ZERO,
+            direction: Vec3::Y,
+            cone_angle: 20.0f32.to_radians(),
+            speed: 3.0,
+            spawn_rate: 200.0,
+            lifetime: 2.0,
+            gravity: vec3(0.0, -9.81, 0.0),
+            start_color: vec4(4.0, 2.0, 0.5, 1.0),
+            end_color: vec4(0.5, 0.1, 0.05, 0.0),
+            size: 0.1,
+        }
+    }
+}
+
+impl ParticleEmitter {
+    /// The number of threads in every workgroup of the simulation, matching `cs_main`.
+    const WORKGROUP_SIZE: u32 = 64;
+
+    /// Creates an emitter holding up to the given number of particles, all of which start dead.
+    pub fn new(
+        device: &Device,
+        pipelines: &Pipelines,
+        settings: EmitterSettings,
+        capacity: u32,
+    ) -> Self {
+        let capacity = capacity.max(1);
+
+        // zero initialized, which leaves every particle dead
+        let particle_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("ParticleEmitter::particle_buffer"),
+            size: (capacity as usize * size_of::<Particle>()) as _,
+            usage: BufferUsages::STORAGE | BufferUsages::VERTEX | BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let uniform_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("ParticleEmitter::uniform_buffer"),
+            size: size_of::<EmitterUniform>() as _,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let simulation_bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("ParticleEmitter::simulation_bind_group"),
+            layout: &pipelines.particle_simulation_bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: particle_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("ParticleEmitter::bind_group"),
+            layout: &pipelines.particle_bind_group_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        Self {
+            settings,
+            capacity,
+            particle_buffer,
+            uniform_buffer,
+            simulation_bind_group,
+            bind_group,
+            pending_time: 0.0,
+            spawn_remainder: 0.0,
+            next_spawn: 0,
+            frame: 0,
+        }
+    }
+
+    /// Returns the maximum number of particles alive at once.
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    /// Returns the buffer holding every particle, which can be copied from.
+    pub fn particle_buffer(&self) -> &Buffer {
+        &self.particle_buffer
+    }
+
+    /// Advances the time simulated by the next frame.
+    pub fn advance(&mut self, dt: f32) {
+        self.pending_time += dt;
+    }
+
+    /// Uploads the settings and the particles to spawn for the next frame, with billboards facing
+    /// the given camera.
+    pub fn write_uniform(&mut self, queue: &Queue, camera: &Camera) {
+        let settings = &self.settings;
+        let dt = std::mem::take(&mut self.pending_time);
+
+        let spawned = self.spawn_remainder + settings.spawn_rate.max(0.0) * dt;
+        let spawn_count = (spawned as u32).min(self.capacity);
+
+        self.spawn_remainder = spawned.fract();
+
+        let uniform = EmitterUniform {
+            position: settings.position,
+            spawn_start: self.next_spawn,
+            direction: settings.direction.normalize_or(Vec3::Y),
+            spawn_count,
+            gravity: settings.gravity,
+            cone_angle: settings.cone_angle,
+            start_color: settings.start_color,
+            end_color: settings.end_color,
+            camera_right: camera.right(),
+            delta_time: dt,
+            camera_up: camera.up(),
+            size: settings.size,
+            speed: settings.speed,
+            lifetime: settings.lifetime,
+            seed: self.frame,
+            capacity: self.capacity,
+        };
+
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniform));
+
+        self.next_spawn = (self.next_spawn + spawn_count) % self.capacity;
+        self.frame = self.frame.wrapping_add(1);
+    }
+
+    /// Records the simulation of every particle into the given compute pass.
+    pub fn simulate(&self, pass: &mut ComputePass, pipelines: &Pipelines) {
+        pass.set_pipeline(&pipelines.particle_simulation_pipeline);
+        pass.set_bind_group(0, &self.simulation_bind_group, &[]);
+        pass.dispatch_workgroups(self.capacity.div_ceil(Self::WORKGROUP_SIZE), 1, 1);
+    }
+
+    /// Draws every particle into the given pass, which must have the particle pipeline and the
+    /// camera bind group set.
+    pub fn draw(&self, pass: &mut RenderPass) {
+        pass.set_bind_group(1, &self.bind_group, &[]);
+        pass.set_vertex_buffer(0, self.particle_buffer.slice(..));
+        pass.draw(0..6, 0..self.capacity);
+    }
+}
@@ -1,69 +1,628 @@
+use std::collections::HashMap;
+
 use wgpu::*;
 
-use crate::renderer::shaders::Shaders;
+use crate::renderer::{
+    depth::DepthTexture,
+    instance::MeshInstance,
+    mesh::{Vertex, VertexLayout},
+    particles::Particle,
+    post::PostEffect,
+    shaders::{ShaderError, ShaderKind, Shaders, reflection::ShaderReflection},
+};
 
 /// Manages the creation and lifecycle of all pipelines and their associated bind group layouts.
+///
+/// Only the bind group layouts are created from the reflected shaders. Pipeline layouts list the
+/// bind group layouts they use by hand (as several pipelines share them), and vertex buffer
+/// layouts are only checked against the inputs of their shaders.
+#[derive(Clone)]
 pub struct Pipelines {
-    /// The pipeline used for rendering a triangle.
-    pub triangle_pipeline: RenderPipeline,
+    /// The pipeline used for rendering every instance of a mesh.
+    pub mesh_pipeline: RenderPipeline,
+    /// The pipeline used for tonemapping the HDR scene onto the target.
+    pub tonemap_pipeline: RenderPipeline,
+    /// The pipelines used for adding bloom to the HDR scene.
+    pub bloom_pipelines: BloomPipelines,
+    /// The pipeline used for drawing particles as additively blended billboards.
+    pub particle_pipeline: RenderPipeline,
+    /// The pipeline used for spawning and moving particles.
+    pub particle_simulation_pipeline: ComputePipeline,
 
     /// The bind group layout for holding a camera's transformation matrix.
     pub camera_bind_group_layout: BindGroupLayout,
+    /// The bind group layout for holding a material's parameters, textures and sampler.
+    pub material_bind_group_layout: BindGroupLayout,
+    /// The bind group layout for holding a single filterable texture and its sampler.
+    pub texture_bind_group_layout: BindGroupLayout,
+    /// The bind group layout for holding the HDR texture, its sampler and the tonemap settings.
+    pub tonemap_bind_group_layout: BindGroupLayout,
+    /// The bind group layout for holding one level of the bloom chain, its sampler and the bloom
+    /// settings.
+    pub bloom_bind_group_layout: BindGroupLayout,
+    /// The bind group layout for holding the texture a post-processing effect reads, its sampler,
+    /// the post-processing settings and the color lookup table.
+    pub post_bind_group_layout: BindGroupLayout,
+    /// The bind group layout for holding an emitter's settings, used when drawing its particles.
+    pub particle_bind_group_layout: BindGroupLayout,
+    /// The bind group layout for holding an emitter's particles and settings, used when
+    /// simulating them.
+    pub particle_simulation_bind_group_layout: BindGroupLayout,
+
+    /// The pipelines used for downsampling each mip level of a texture, by texture format.
+    mipmap_pipelines: HashMap<TextureFormat, RenderPipeline>,
+    /// The pipelines applying each post-processing effect onto the target's format.
+    post_pipelines: HashMap<PostEffect, RenderPipeline>,
+
+    /// The formats of the textures rendered into by the pipelines.
+    formats: TargetFormats,
+}
+
+/// The pipelines used for adding bloom to the HDR scene, one for each step of the bloom chain.
+#[derive(Clone)]
+pub struct BloomPipelines {
+    /// Extracts the bright parts of the HDR scene into the first level of the chain.
+    pub prefilter: RenderPipeline,
+    /// Downsamples one level of the chain into the next.
+    pub downsample: RenderPipeline,
+    /// Upsamples one level of the chain, adding it onto the previous level.
+    pub upsample: RenderPipeline,
+    /// Upsamples the first level of the chain, adding it onto the HDR scene scaled by the blend
+    /// constant.
+    pub composite: RenderPipeline,
+}
+
+/// The formats (and sample count) of the textures the main render pass draws into, along with
+/// the format of the target the result is finally written into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TargetFormats {
+    /// The format of the (HDR) color texture.
+    pub color: TextureFormat,
+    /// The format of the depth texture.
+    pub depth: TextureFormat,
+    /// The number of samples taken per pixel, resolved onto the color texture if more than one.
+    pub sample_count: u32,
+    /// The format of the target the color texture is tonemapped onto.
+    pub output: TextureFormat,
 }
 
 impl Pipelines {
-    /// Creates all the [`Pipelines`] given their associated shaders.
-    pub fn new(device: &Device, shaders: &Shaders) -> Self {
-        let camera_bind_group_layout =
-            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
-                label: Some("Pipelines::camera_bind_group_layout"),
-                entries: &[BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: ShaderStages::VERTEX,
-                    ty: BindingType::Buffer {
-                        ty: BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                }],
-            });
+    /// Creates all the [`Pipelines`] given their associated shaders, rendering into targets of
+    /// the given formats.
+    pub fn new(device: &Device, shaders: &Shaders, formats: TargetFormats) -> Self {
+        let mesh_reflection = shaders.reflection(ShaderKind::Mesh);
+        let blit_reflection = shaders.reflection(ShaderKind::Blit);
+        let tonemap_reflection = shaders.reflection(ShaderKind::Tonemap);
+        let bloom_reflection = shaders.reflection(ShaderKind::Bloom);
+        let post_reflection = shaders.reflection(ShaderKind::Post);
+        let particle_reflection = shaders.reflection(ShaderKind::Particle);
+        let particle_simulation_reflection = shaders.reflection(ShaderKind::ParticleSimulation);
+
+        let camera_bind_group_layout = Self::create_bind_group_layout(
+            device,
+            "Pipelines::camera_bind_group_layout",
+            mesh_reflection,
+            0,
+        );
+
+        let material_bind_group_layout = Self::create_bind_group_layout(
+            device,
+            "Pipelines::material_bind_group_layout",
+            mesh_reflection,
+            1,
+        );
+
+        let texture_bind_group_layout = Self::create_bind_group_layout(
+            device,
+            "Pipelines::texture_bind_group_layout",
+            blit_reflection,
+            0,
+        );
+
+        let tonemap_bind_group_layout = Self::create_bind_group_layout(
+            device,
+            "Pipelines::tonemap_bind_group_layout",
+            tonemap_reflection,
+            0,
+        );
+
+        let bloom_bind_group_layout = Self::create_bind_group_layout(
+            device,
+            "Pipelines::bloom_bind_group_layout",
+            bloom_reflection,
+            0,
+        );
+
+        let post_bind_group_layout = Self::create_bind_group_layout(
+            device,
+            "Pipelines::post_bind_group_layout",
+            post_reflection,
+            0,
+        );
+
+        let particle_bind_group_layout = Self::create_bind_group_layout(
+            device,
+            "Pipelines::particle_bind_group_layout",
+            particle_reflection,
+            1,
+        );
+
+        let particle_simulation_bind_group_layout = Self::create_bind_group_layout(
+            device,
+            "Pipelines::particle_simulation_bind_group_layout",
+            particle_simulation_reflection,
+            0,
+        );
+
+        let mesh_pipeline = Self::create_mesh_pipeline(
+            device,
+            shaders,
+            &camera_bind_group_layout,
+            &material_bind_group_layout,
+            formats,
+        )
+        .unwrap_or_else(|e| panic!("invalid embedded shader: {e}"));
+
+        let tonemap_pipeline = Self::create_fullscreen_pipeline(
+            device,
+            "Pipelines::tonemap_pipeline",
+            (&shaders.tonemap_shader, "fs_main"),
+            &tonemap_bind_group_layout,
+            formats.output.into(),
+        );
+
+        let bloom_pipelines =
+            Self::create_bloom_pipelines(device, shaders, &bloom_bind_group_layout, formats);
+
+        let mipmap_pipelines =
+            Self::create_mipmap_pipelines(device, shaders, &texture_bind_group_layout);
+
+        let post_pipelines =
+            Self::create_post_pipelines(device, shaders, &post_bind_group_layout, formats);
+
+        let particle_pipeline = Self::create_particle_pipeline(
+            device,
+            shaders,
+            &camera_bind_group_layout,
+            &particle_bind_group_layout,
+            formats,
+        )
+        .unwrap_or_else(|e| panic!("invalid embedded shader: {e}"));
+
+        let particle_simulation_pipeline = Self::create_particle_simulation_pipeline(
+            device,
+            shaders,
+            &particle_simulation_bind_group_layout,
+        );
+
+        Self {
+            mesh_pipeline,
+            tonemap_pipeline,
+            bloom_pipelines,
+            particle_pipeline,
+            particle_simulation_pipeline,
+            camera_bind_group_layout,
+            material_bind_group_layout,
+            texture_bind_group_layout,
+            tonemap_bind_group_layout,
+            bloom_bind_group_layout,
+            post_bind_group_layout,
+            particle_bind_group_layout,
+            particle_simulation_bind_group_layout,
+            mipmap_pipelines,
+            post_pipelines,
+            formats,
+        }
+    }
+
+    /// Recreates every pipeline using the given shader (usually after it has been reloaded).
+    ///
+    /// The bind group layouts are kept as they are, as existing bind groups depend on them.
+    pub fn rebuild(
+        &mut self,
+        device: &Device,
+        shaders: &Shaders,
+        kind: ShaderKind,
+    ) -> Result<(), ShaderError> {
+        match kind {
+            ShaderKind::Mesh => {
+                self.mesh_pipeline = Self::create_mesh_pipeline(
+                    device,
+                    shaders,
+                    &self.camera_bind_group_layout,
+                    &self.material_bind_group_layout,
+                    self.formats,
+                )?;
+            }
+            ShaderKind::Blit => {
+                self.mipmap_pipelines =
+                    Self::create_mipmap_pipelines(device, shaders, &self.texture_bind_group_layout);
+            }
+            ShaderKind::Tonemap => {
+                self.tonemap_pipeline = Self::create_fullscreen_pipeline(
+                    device,
+                    "Pipelines::tonemap_pipeline",
+                    (&shaders.tonemap_shader, "fs_main"),
+                    &self.tonemap_bind_group_layout,
+                    self.formats.output.into(),
+                );
+            }
+            ShaderKind::Bloom => {
+                self.bloom_pipelines = Self::create_bloom_pipelines(
+                    device,
+                    shaders,
+                    &self.bloom_bind_group_layout,
+                    self.formats,
+                );
+            }
+            ShaderKind::Post => {
+                self.post_pipelines = Self::create_post_pipelines(
+                    device,
+                    shaders,
+                    &self.post_bind_group_layout,
+                    self.formats,
+                );
+            }
+            ShaderKind::Particle => {
+                self.particle_pipeline = Self::create_particle_pipeline(
+                    device,
+                    shaders,
+                    &self.camera_bind_group_layout,
+                    &self.particle_bind_group_layout,
+                    self.formats,
+                )?;
+            }
+            ShaderKind::ParticleSimulation => {
+                self.particle_simulation_pipeline = Self::create_particle_simulation_pipeline(
+                    device,
+                    shaders,
+                    &self.particle_simulation_bind_group_layout,
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the formats of the textures rendered into by the pipelines.
+    pub fn formats(&self) -> TargetFormats {
+        self.formats
+    }
 
-        let triangle_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
-            label: Some("Pipelines::triangle_pipeline_layout"),
-            bind_group_layouts: &[&camera_bind_group_layout],
+    /// Changes the formats of the textures rendered into, recreating every pipeline drawing into
+    /// them.
+    pub fn set_formats(&mut self, device: &Device, shaders: &Shaders, formats: TargetFormats) {
+        self.formats = formats;
+
+        for kind in [
+            ShaderKind::Mesh,
+            ShaderKind::Tonemap,
+            ShaderKind::Bloom,
+            ShaderKind::Post,
+            ShaderKind::Particle,
+        ] {
+            self.rebuild(device, shaders, kind)
+                .unwrap_or_else(|e| panic!("failed to rebuild pipelines: {e}"));
+        }
+    }
+
+    /// Creates the pipeline used for rendering meshes.
+    fn create_mesh_pipeline(
+        device: &Device,
+        shaders: &Shaders,
+        camera_bind_group_layout: &BindGroupLayout,
+        material_bind_group_layout: &BindGroupLayout,
+        formats: TargetFormats,
+    ) -> Result<RenderPipeline, ShaderError> {
+        shaders
+            .reflection(ShaderKind::Mesh)
+            .check_vertex_attributes(&[Vertex::ATTRIBUTES, MeshInstance::ATTRIBUTES].concat())
+            .map_err(|message| ShaderError {
+                kind: ShaderKind::Mesh,
+                message,
+            })?;
+
+        let mesh_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Pipelines::mesh_pipeline_layout"),
+            bind_group_layouts: &[camera_bind_group_layout, material_bind_group_layout],
             push_constant_ranges: &[],
         });
 
-        let triangle_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
-            label: Some("Pipelines::triangle_pipeline"),
-            layout: Some(&triangle_pipeline_layout),
+        Ok(device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Pipelines::mesh_pipeline"),
+            layout: Some(&mesh_pipeline_layout),
             vertex: VertexState {
-                module: &shaders.triangle_shader,
+                module: &shaders.mesh_shader,
                 entry_point: Some("vs_main"),
                 compilation_options: PipelineCompilationOptions::default(),
-                buffers: &[],
+                buffers: &[
+                    Vertex::layout(),
+                    VertexBufferLayout {
+                        step_mode: VertexStepMode::Instance,
+                        ..MeshInstance::layout()
+                    },
+                ],
             },
             fragment: Some(FragmentState {
-                module: &shaders.triangle_shader,
+                module: &shaders.mesh_shader,
                 entry_point: Some("fs_main"),
                 compilation_options: PipelineCompilationOptions::default(),
                 targets: &[Some(ColorTargetState {
-                    format: TextureFormat::Bgra8Unorm,
+                    format: formats.color,
                     blend: None,
                     write_mask: ColorWrites::ALL,
                 })],
             }),
             primitive: PrimitiveState::default(),
-            multisample: MultisampleState::default(),
-            depth_stencil: None,
+            multisample: MultisampleState {
+                count: formats.sample_count,
+                ..Default::default()
+            },
+            depth_stencil: Some(DepthTexture::depth_stencil_state(formats.depth)),
             multiview: None,
             cache: None,
+        }))
+    }
+
+    /// Creates the pipeline used for drawing particles, with every particle being an instance of
+    /// a billboard.
+    ///
+    /// Particles are blended additively and tested against (but don't write) depth, so they
+    /// don't need to be sorted.
+    fn create_particle_pipeline(
+        device: &Device,
+        shaders: &Shaders,
+        camera_bind_group_layout: &BindGroupLayout,
+        particle_bind_group_layout: &BindGroupLayout,
+        formats: TargetFormats,
+    ) -> Result<RenderPipeline, ShaderError> {
+        shaders
+            .reflection(ShaderKind::Particle)
+            .check_vertex_attributes(Particle::ATTRIBUTES)
+            .map_err(|message| ShaderError {
+                kind: ShaderKind::Particle,
+                message,
+            })?;
+
+        let particle_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Pipelines::particle_pipeline_layout"),
+            bind_group_layouts: &[camera_bind_group_layout, particle_bind_group_layout],
+            push_constant_ranges: &[],
         });
 
-        Self {
-            triangle_pipeline,
-            camera_bind_group_layout,
+        Ok(device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Pipelines::particle_pipeline"),
+            layout: Some(&particle_pipeline_layout),
+            vertex: VertexState {
+                module: &shaders.particle_shader,
+                entry_point: Some("vs_main"),
+                compilation_options: PipelineCompilationOptions::default(),
+                buffers: &[VertexBufferLayout {
+                    step_mode: VertexStepMode::Instance,
+                    ..Particle::layout()
+                }],
+            },
+            fragment: Some(FragmentState {
+                module: &shaders.particle_shader,
+                entry_point: Some("fs_main"),
+                compilation_options: PipelineCompilationOptions::default(),
+                targets: &[Some(ColorTargetState {
+                    format: formats.color,
+                    blend: Some(BlendState {
+                        color: BlendComponent {
+                            src_factor: BlendFactor::SrcAlpha,
+                            dst_factor: BlendFactor::One,
+                            operation: BlendOperation::Add,
+                        },
+                        alpha: BlendComponent {
+                            src_factor: BlendFactor::Zero,
+                            dst_factor: BlendFactor::One,
+                            operation: BlendOperation::Add,
+                        },
+                    }),
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            multisample: MultisampleState {
+                count: formats.sample_count,
+                ..Default::default()
+            },
+            depth_stencil: Some(DepthStencilState {
+                depth_write_enabled: false,
+                ..DepthTexture::depth_stencil_state(formats.depth)
+            }),
+            multiview: None,
+            cache: None,
+        }))
+    }
+
+    /// Creates the pipeline used for simulating particles.
+    fn create_particle_simulation_pipeline(
+        device: &Device,
+        shaders: &Shaders,
+        particle_simulation_bind_group_layout: &BindGroupLayout,
+    ) -> ComputePipeline {
+        let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Pipelines::particle_simulation_pipeline_layout"),
+            bind_group_layouts: &[particle_simulation_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some("Pipelines::particle_simulation_pipeline"),
+            layout: Some(&layout),
+            module: &shaders.particle_simulation_shader,
+            entry_point: Some("cs_main"),
+            compilation_options: PipelineCompilationOptions::default(),
+            cache: None,
+        })
+    }
+
+    /// Creates the pipelines used for downsampling textures, for every supported format.
+    fn create_mipmap_pipelines(
+        device: &Device,
+        shaders: &Shaders,
+        texture_bind_group_layout: &BindGroupLayout,
+    ) -> HashMap<TextureFormat, RenderPipeline> {
+        [
+            TextureFormat::Rgba8Unorm,
+            TextureFormat::Rgba8UnormSrgb,
+            TextureFormat::Rgba16Float,
+        ]
+        .into_iter()
+        .map(|format| {
+            let pipeline = Self::create_fullscreen_pipeline(
+                device,
+                "Pipelines::mipmap_pipeline",
+                (&shaders.blit_shader, "fs_main"),
+                texture_bind_group_layout,
+                format.into(),
+            );
+
+            (format, pipeline)
+        })
+        .collect()
+    }
+
+    /// Creates the pipelines used for each step of the bloom chain, which is kept in the format
+    /// of the HDR color texture.
+    fn create_bloom_pipelines(
+        device: &Device,
+        shaders: &Shaders,
+        bloom_bind_group_layout: &BindGroupLayout,
+        formats: TargetFormats,
+    ) -> BloomPipelines {
+        let pipeline = |label, entry_point, blend| {
+            Self::create_fullscreen_pipeline(
+                device,
+                label,
+                (&shaders.bloom_shader, entry_point),
+                bloom_bind_group_layout,
+                ColorTargetState {
+                    format: formats.color,
+                    blend,
+                    write_mask: ColorWrites::ALL,
+                },
+            )
+        };
+
+        let additive = |factor| BlendState {
+            color: BlendComponent {
+                src_factor: factor,
+                dst_factor: BlendFactor::One,
+                operation: BlendOperation::Add,
+            },
+            alpha: BlendComponent {
+                src_factor: BlendFactor::Zero,
+                dst_factor: BlendFactor::One,
+                operation: BlendOperation::Add,
+            },
+        };
+
+        BloomPipelines {
+            prefilter: pipeline("Pipelines::bloom_prefilter_pipeline", "fs_prefilter", None),
+            downsample: pipeline(
+                "Pipelines::bloom_downsample_pipeline",
+                "fs_downsample",
+                None,
+            ),
+            upsample: pipeline(
+                "Pipelines::bloom_upsample_pipeline",
+                "fs_upsample",
+                Some(additive(BlendFactor::One)),
+            ),
+            composite: pipeline(
+                "Pipelines::bloom_composite_pipeline",
+                "fs_upsample",
+                Some(additive(BlendFactor::Constant)),
+            ),
         }
     }
+
+    /// Creates the pipelines applying each post-processing effect, drawing into the format of
+    /// the target.
+    fn create_post_pipelines(
+        device: &Device,
+        shaders: &Shaders,
+        post_bind_group_layout: &BindGroupLayout,
+        formats: TargetFormats,
+    ) -> HashMap<PostEffect, RenderPipeline> {
+        PostEffect::ALL
+            .into_iter()
+            .map(|effect| {
+                let pipeline = Self::create_fullscreen_pipeline(
+                    device,
+                    &format!("Pipelines::post_pipeline({effect:?})"),
+                    (&shaders.post_shader, effect.entry_point()),
+                    post_bind_group_layout,
+                    formats.output.into(),
+                );
+
+                (effect, pipeline)
+            })
+            .collect()
+    }
+
+    /// Creates a pipeline drawing a single fullscreen triangle (see `fullscreen.wgsl`) with the
+    /// given shader, fragment entry point and bind group layout, into the given color target.
+    fn create_fullscreen_pipeline(
+        device: &Device,
+        label: &str,
+        (module, fragment_entry_point): (&ShaderModule, &str),
+        bind_group_layout: &BindGroupLayout,
+        target: ColorTargetState,
+    ) -> RenderPipeline {
+        let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some(&format!("{label}_layout")),
+            bind_group_layouts: &[bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some(label),
+            layout: Some(&layout),
+            vertex: VertexState {
+                module,
+                entry_point: Some("vs_main"),
+                compilation_options: PipelineCompilationOptions::default(),
+                buffers: &[],
+            },
+            fragment: Some(FragmentState {
+                module,
+                entry_point: Some(fragment_entry_point),
+                compilation_options: PipelineCompilationOptions::default(),
+                targets: &[Some(target)],
+            }),
+            primitive: PrimitiveState::default(),
+            multisample: MultisampleState::default(),
+            depth_stencil: None,
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    /// Creates the layout of one of the bind groups declared by a shader.
+    pub(crate) fn create_bind_group_layout(
+        device: &Device,
+        label: &str,
+        reflection: &ShaderReflection,
+        group: usize,
+    ) -> BindGroupLayout {
+        device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some(label),
+            entries: reflection.bind_groups.get(group).map_or(&[], Vec::as_slice),
+        })
+    }
+
+    /// Returns the pipeline for generating mipmaps of textures with the given format, if the
+    /// format is supported.
+    pub fn mipmap_pipeline(&self, format: TextureFormat) -> Option<&RenderPipeline> {
+        self.mipmap_pipelines.get(&format)
+    }
+
+    /// Returns the pipeline applying the given post-processing effect.
+    pub fn post_pipeline(&self, effect: PostEffect) -> &RenderPipeline {
+        &self.post_pipelines[&effect]
+    }
 }
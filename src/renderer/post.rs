@@ -0,0 +1,704 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use anyhow::bail;
+use bytemuck::{Pod, Zeroable};
+use image::{Rgba, RgbaImage};
+use wgpu::*;
+
+use crate::renderer::{
+    graph::{RenderGraph, TextureHandle, TransientTexture},
+    pipelines::Pipelines,
+    texture::ImageTexture,
+    tonemap::HdrTarget,
+};
+
+/// A post-processing effect applied after tonemapping, in the order they are applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PostEffect {
+    /// Smooths aliased edges by blurring along them.
+    Fxaa,
+    /// Pulls the color channels apart towards the edges of the image.
+    ChromaticAberration,
+    /// Maps every color through a 3D lookup table.
+    ColorGrading,
+    /// Darkens the image towards its corners.
+    Vignette,
+}
+
+/// Controls the bloom added to the bright parts of the HDR scene, before tonemapping.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BloomSettings {
+    /// Whether bloom is applied.
+    pub enabled: bool,
+    /// The brightness above which colors start to bloom.
+    pub threshold: f32,
+    /// How far below the `threshold` colors fade into the bloom, rather than cutting off sharply.
+    pub knee: f32,
+    /// How strongly the bloom is added onto the scene.
+    pub intensity: f32,
+}
+
+/// Controls the fast approximate anti-aliasing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FxaaSettings {
+    /// Whether FXAA is applied.
+    pub enabled: bool,
+    /// The furthest distance (in texels) blurred along an edge.
+    pub span: f32,
+}
+
+/// Controls the chromatic aberration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChromaticAberrationSettings {
+    /// Whether chromatic aberration is applied.
+    pub enabled: bool,
+    /// How far the red and blue channels are offset at the edges, relative to the image size.
+    pub strength: f32,
+}
+
+/// Controls the color grading through the renderer's color lookup table.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorGradingSettings {
+    /// Whether color grading is applied.
+    pub enabled: bool,
+    /// How much the graded colors replace the original colors, from 0.0 to 1.0.
+    pub intensity: f32,
+}
+
+/// Controls the vignette.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VignetteSettings {
+    /// Whether the vignette is applied.
+    pub enabled: bool,
+    /// How much the corners are darkened, from 0.0 to 1.0.
+    pub intensity: f32,
+    /// The distance from the center (1.0 being the corners) at which the vignette starts.
+    pub radius: f32,
+    /// The distance over which the vignette fades in.
+    pub smoothness: f32,
+}
+
+/// Controls every post-processing effect, each of which can be toggled separately.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PostSettings {
+    /// See [`BloomSettings`].
+    pub bloom: BloomSettings,
+    /// See [`FxaaSettings`].
+    pub fxaa: FxaaSettings,
+    /// See [`ChromaticAberrationSettings`].
+    pub chromatic_aberration: ChromaticAberrationSettings,
+    /// See [`ColorGradingSettings`].
+    pub color_grading: ColorGradingSettings,
+    /// See [`VignetteSettings`].
+    pub vignette: VignetteSettings,
+}
+
+/// The layout of the bloom uniform buffer, matching `Bloom` in the bloom shader.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+struct BloomUniform {
+    threshold: f32,
+    knee: f32,
+}
+
+/// The layout of the post-processing uniform buffer, matching `Post` in the post shader.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+struct PostUniform {
+    chromatic_aberration: f32,
+    color_grading_intensity: f32,
+    vignette_intensity: f32,
+    vignette_radius: f32,
+    vignette_smoothness: f32,
+    fxaa_span: f32,
+    linear: u32,
+}
+
+/// A 3D lookup table mapping display encoded colors onto graded colors.
+pub struct ColorLut {
+    /// The 3D texture holding the graded colors, indexed by red, green and blue.
+    pub texture: Texture,
+    /// A view into the whole `texture`.
+    pub view: TextureView,
+}
+
+/// The textures and bind groups used to apply bloom and every post-processing effect, matching
+/// the size of the target.
+pub struct PostProcessing {
+    /// Every level of the bloom chain, each half the size of the previous one (starting at half
+    /// the size of the target).
+    bloom_views: Vec<TextureView>,
+    /// The bind group reading the HDR texture, used to extract the first level of the chain.
+    hdr_bind_group: BindGroup,
+    /// The bind groups reading each level of the bloom chain.
+    bloom_bind_groups: Vec<BindGroup>,
+    /// The uniform buffer holding the current [`BloomSettings`].
+    bloom_buffer: Buffer,
+    /// The uniform buffer holding the settings of every effect.
+    post_buffer: Buffer,
+    /// The sampler effects read the previous image with.
+    sampler: Sampler,
+    /// A view into the color lookup table read by color grading.
+    lut_view: TextureView,
+    /// The bind groups effects read the previous image with, by the transient texture read.
+    post_bind_groups: RefCell<PostBindGroups>,
+    /// The size of the target (in pixels).
+    size: (u32, u32),
+}
+
+/// The bind groups reading the transient textures effects are applied onto, kept across frames
+/// for as long as the render graph keeps reusing the same textures.
+#[derive(Default)]
+struct PostBindGroups {
+    /// The bind groups used by the current frame.
+    current: HashMap<TextureView, BindGroup>,
+    /// The bind groups used by the previous frame, dropped unless the current frame uses them.
+    previous: HashMap<TextureView, BindGroup>,
+}
+
+impl PostEffect {
+    /// Every post-processing effect, in the order they are applied.
+    pub const ALL: [Self; 4] = [
+        Self::Fxaa,
+        Self::ChromaticAberration,
+        Self::ColorGrading,
+        Self::Vignette,
+    ];
+
+    /// Returns the human readable name of the effect.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Fxaa => "FXAA",
+            Self::ChromaticAberration => "Chromatic Aberration",
+            Self::ColorGrading => "Color Grading",
+            Self::Vignette => "Vignette",
+        }
+    }
+
+    /// Returns the fragment entry point of the post shader applying the effect.
+    pub fn entry_point(self) -> &'static str {
+        match self {
+            Self::Fxaa => "fs_fxaa",
+            Self::ChromaticAberration => "fs_chromatic_aberration",
+            Self::ColorGrading => "fs_color_grading",
+            Self::Vignette => "fs_vignette",
+        }
+    }
+}
+
+impl Default for BloomSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: 1.0,
+            knee: 0.5,
+            intensity: 0.3,
+        }
+    }
+}
+
+impl Default for FxaaSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            span: 8.0,
+        }
+    }
+}
+
+impl Default for ChromaticAberrationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            strength: 0.01,
+        }
+    }
+}
+
+impl Default for ColorGradingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            intensity: 1.0,
+        }
+    }
+}
+
+impl Default for VignetteSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            intensity: 0.5,
+            radius: 0.5,
+            smoothness: 0.5,
+        }
+    }
+}
+
+impl PostSettings {
+    /// Returns whether the given effect is applied.
+    pub fn is_enabled(&self, effect: PostEffect) -> bool {
+        match effect {
+            PostEffect::Fxaa => self.fxaa.enabled,
+            PostEffect::ChromaticAberration => self.chromatic_aberration.enabled,
+            PostEffect::ColorGrading => self.color_grading.enabled,
+            PostEffect::Vignette => self.vignette.enabled,
+        }
+    }
+
+    /// Returns every applied effect, in the order they are applied.
+    pub fn enabled_effects(&self) -> impl Iterator<Item = PostEffect> + '_ {
+        PostEffect::ALL
+            .into_iter()
+            .filter(|&effect| self.is_enabled(effect))
+    }
+}
+
+impl ColorLut {
+    /// The size of every side of the identity lookup table.
+    pub const IDENTITY_SIZE: u32 = 16;
+
+    /// Creates a lookup table which leaves every color unchanged.
+    pub fn identity(device: &Device, queue: &Queue) -> Self {
+        let size = Self::IDENTITY_SIZE;
+        let scale = |channel: u32| (channel * 255 / (size - 1)) as u8;
+
+        let strip = RgbaImage::from_fn(size * size, size, |x, y| {
+            Rgba([scale(x % size), scale(y), scale(x / size), 255])
+        });
+
+        Self::from_strip(device, queue, &strip).expect("the identity strip should be valid")
+    }
+
+    /// Uploads a lookup table stored as a horizontal strip of `size` squares of `size` by `size`
+    /// pixels (the common format exported by image editors), with red increasing to the right
+    /// within each square, green increasing downwards and blue increasing from square to square.
+    ///
+    /// Fails if the image is not laid out as such a strip.
+    pub fn from_strip(device: &Device, queue: &Queue, strip: &RgbaImage) -> anyhow::Result<Self> {
+        let (width, height) = strip.dimensions();
+        let size = height;
+
+        if size < 2 || width != size * size {
+            bail!(
+                "a color lookup table of {width}x{height} pixels is not a strip of {height} \
+                 squares"
+            );
+        }
+
+        // every square becomes one layer of the 3D texture, made of contiguous rows
+        let pixels = (0..size)
+            .flat_map(|blue| {
+                (0..size).flat_map(move |green| {
+                    (0..size).flat_map(move |red| strip.get_pixel(blue * size + red, green).0)
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let extent = Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: size,
+        };
+
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("ColorLut::texture"),
+            size: extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D3,
+            format: TextureFormat::Rgba8Unorm,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        queue.write_texture(
+            texture.as_image_copy(),
+            &pixels,
+            TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(size * 4),
+                rows_per_image: Some(size),
+            },
+            extent,
+        );
+
+        let view = texture.create_view(&TextureViewDescriptor::default());
+
+        Ok(Self { texture, view })
+    }
+}
+
+impl PostProcessing {
+    /// The maximum number of levels in the bloom chain, which bounds how far bloom spreads.
+    pub const MAX_BLOOM_LEVELS: u32 = 6;
+
+    /// Creates the textures used for post-processing a target of the given size, reading the
+    /// given HDR target and color lookup table with the given sampler.
+    pub fn new(
+        device: &Device,
+        pipelines: &Pipelines,
+        sampler: &Sampler,
+        (hdr, lut): (&HdrTarget, &ColorLut),
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let bloom_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("PostProcessing::bloom_buffer"),
+            size: size_of::<BloomUniform>() as _,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let post_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("PostProcessing::post_buffer"),
+            size: size_of::<PostUniform>() as _,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bloom_bind_group = |label, view: &TextureView| {
+            device.create_bind_group(&BindGroupDescriptor {
+                label: Some(label),
+                layout: &pipelines.bloom_bind_group_layout,
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: BindingResource::TextureView(view),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::Sampler(sampler),
+                    },
+                    BindGroupEntry {
+                        binding: 2,
+                        resource: bloom_buffer.as_entire_binding(),
+                    },
+                ],
+            })
+        };
+
+        let bloom_width = (width / 2).max(1);
+        let bloom_height = (height / 2).max(1);
+        let bloom_levels =
+            ImageTexture::mip_level_count(bloom_width, bloom_height).min(Self::MAX_BLOOM_LEVELS);
+
+        let bloom_texture = device.create_texture(&TextureDescriptor {
+            label: Some("PostProcessing::bloom_texture"),
+            size: Extent3d {
+                width: bloom_width,
+                height: bloom_height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: bloom_levels,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: HdrTarget::FORMAT,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+
+        let bloom_views = (0..bloom_levels)
+            .map(|level| {
+                bloom_texture.create_view(&TextureViewDescriptor {
+                    base_mip_level: level,
+                    mip_level_count: Some(1),
+                    ..Default::default()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let hdr_bind_group = bloom_bind_group("PostProcessing::hdr_bind_group", &hdr.view);
+
+        let bloom_bind_groups = bloom_views
+            .iter()
+            .map(|view| bloom_bind_group("PostProcessing::bloom_bind_group", view))
+            .collect();
+
+        Self {
+            bloom_views,
+            hdr_bind_group,
+            bloom_bind_groups,
+            bloom_buffer,
+            post_buffer,
+            sampler: sampler.clone(),
+            lut_view: lut.view.clone(),
+            post_bind_groups: RefCell::default(),
+            size: (width, height),
+        }
+    }
+
+    /// Uploads the settings used by the next post-processing passes onto a target of the given
+    /// format.
+    pub fn write_settings(&self, queue: &Queue, settings: &PostSettings, format: TextureFormat) {
+        let bloom = BloomUniform {
+            threshold: settings.bloom.threshold,
+            knee: settings.bloom.knee,
+        };
+
+        let post = PostUniform {
+            chromatic_aberration: settings.chromatic_aberration.strength,
+            color_grading_intensity: settings.color_grading.intensity,
+            vignette_intensity: settings.vignette.intensity,
+            vignette_radius: settings.vignette.radius,
+            vignette_smoothness: settings.vignette.smoothness,
+            fxaa_span: settings.fxaa.span,
+            // sRGB targets decode the colors read from them automatically
+            linear: format.is_srgb() as u32,
+        };
+
+        queue.write_buffer(&self.bloom_buffer, 0, bytemuck::bytes_of(&bloom));
+        queue.write_buffer(&self.post_buffer, 0, bytemuck::bytes_of(&post));
+    }
+
+    /// Adds every enabled pass turning the HDR scene into the final image on the given output:
+    /// bloom, tonemapping and then every other effect, each drawing into a transient texture read
+    /// by the next one.
+    pub fn add_passes<'a>(
+        &'a self,
+        graph: &mut RenderGraph<'a>,
+        pipelines: &'a Pipelines,
+        hdr_target: &'a HdrTarget,
+        hdr: TextureHandle,
+        settings: &PostSettings,
+        output: TextureHandle,
+    ) {
+        if settings.bloom.enabled {
+            self.add_bloom_passes(graph, pipelines, hdr, settings.bloom.intensity);
+        }
+
+        let (width, height) = self.size;
+
+        let intermediate = TransientTexture {
+            width,
+            height,
+            format: pipelines.formats().output,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+        };
+
+        let effects = settings.enabled_effects().collect::<Vec<_>>();
+
+        // textures no longer read since the previous frame (such as the intermediate textures of
+        // disabled effects) are dropped along with their bind groups
+        let mut bind_groups = self.post_bind_groups.borrow_mut();
+        bind_groups.previous = std::mem::take(&mut bind_groups.current);
+        drop(bind_groups);
+
+        let mut source = match effects.is_empty() {
+            true => output,
+            false => graph.create_texture("tonemapped", intermediate),
+        };
+
+        graph.add_pass("tonemap", |pass| {
+            pass.read(hdr).write(source);
+
+            move |ctx| hdr_target.tonemap(ctx.encoder, pipelines, ctx.texture(source))
+        });
+
+        for (i, &effect) in effects.iter().enumerate() {
+            let target = match i + 1 == effects.len() {
+                true => output,
+                false => graph.create_texture(effect.name(), intermediate),
+            };
+
+            graph.add_pass(effect.name(), |pass| {
+                pass.read(source).write(target);
+
+                move |ctx| {
+                    let bind_group =
+                        self.post_bind_group(ctx.device, pipelines, ctx.texture(source));
+                    let view = ctx.texture(target);
+
+                    Self::draw(
+                        ctx.encoder,
+                        effect.name(),
+                        view,
+                        LoadOp::Clear(Color::BLACK),
+                    )
+                    .draw_with(pipelines.post_pipeline(effect), &bind_group);
+                }
+            });
+
+            source = target;
+        }
+    }
+
+    /// Returns the bind group an effect reads the previous image from, only creating it if the
+    /// view wasn't read during the current or previous frame.
+    fn post_bind_group(
+        &self,
+        device: &Device,
+        pipelines: &Pipelines,
+        view: &TextureView,
+    ) -> BindGroup {
+        let mut bind_groups = self.post_bind_groups.borrow_mut();
+
+        if let Some(bind_group) = bind_groups.current.get(view) {
+            return bind_group.clone();
+        }
+
+        let bind_group = match bind_groups.previous.remove(view) {
+            Some(bind_group) => bind_group,
+            None => self.create_post_bind_group(device, pipelines, view),
+        };
+
+        bind_groups.current.insert(view.clone(), bind_group.clone());
+
+        bind_group
+    }
+
+    /// Creates the bind group an effect reads the previous image from.
+    fn create_post_bind_group(
+        &self,
+        device: &Device,
+        pipelines: &Pipelines,
+        view: &TextureView,
+    ) -> BindGroup {
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some("PostProcessing::post_bind_group"),
+            layout: &pipelines.post_bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(&self.sampler),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: self.post_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: BindingResource::TextureView(&self.lut_view),
+                },
+            ],
+        })
+    }
+
+    /// Adds the passes extracting the bright parts of the HDR scene, blurring them down and back
+    /// up the bloom chain and adding them back onto the HDR texture.
+    fn add_bloom_passes<'a>(
+        &'a self,
+        graph: &mut RenderGraph<'a>,
+        pipelines: &'a Pipelines,
+        hdr: TextureHandle,
+        intensity: f32,
+    ) {
+        let bloom = &pipelines.bloom_pipelines;
+        let clear = LoadOp::Clear(Color::BLACK);
+
+        let levels = self
+            .bloom_views
+            .iter()
+            .enumerate()
+            .map(|(level, view)| graph.import_texture(&format!("bloom_level_{level}"), view))
+            .collect::<Vec<_>>();
+
+        graph.add_pass("bloom_prefilter", |pass| {
+            pass.read(hdr).write(levels[0]);
+
+            move |ctx| {
+                Self::draw(ctx.encoder, "bloom_prefilter", &self.bloom_views[0], clear)
+                    .draw_with(&bloom.prefilter, &self.hdr_bind_group);
+            }
+        });
+
+        for level in 1..levels.len() {
+            graph.add_pass("bloom_downsample", |pass| {
+                pass.read(levels[level - 1]).write(levels[level]);
+
+                move |ctx| {
+                    Self::draw(
+                        ctx.encoder,
+                        "bloom_downsample",
+                        &self.bloom_views[level],
+                        clear,
+                    )
+                    .draw_with(&bloom.downsample, &self.bloom_bind_groups[level - 1]);
+                }
+            });
+        }
+
+        for level in (1..levels.len()).rev() {
+            graph.add_pass("bloom_upsample", |pass| {
+                pass.read(levels[level]).write(levels[level - 1]);
+
+                move |ctx| {
+                    Self::draw(
+                        ctx.encoder,
+                        "bloom_upsample",
+                        &self.bloom_views[level - 1],
+                        LoadOp::Load,
+                    )
+                    .draw_with(&bloom.upsample, &self.bloom_bind_groups[level]);
+                }
+            });
+        }
+
+        graph.add_pass("bloom_composite", |pass| {
+            pass.read(levels[0]).write(hdr);
+
+            move |ctx| {
+                let view = ctx.texture(hdr);
+                let mut pass = Self::draw(ctx.encoder, "bloom_composite", view, LoadOp::Load);
+
+                // every level has been added up, so they are averaged to keep the intensity
+                // independent of the length of the chain
+                let scale = (intensity / self.bloom_views.len() as f32) as f64;
+
+                pass.set_blend_constant(Color {
+                    r: scale,
+                    g: scale,
+                    b: scale,
+                    a: 1.0,
+                });
+
+                pass.draw_with(&bloom.composite, &self.bloom_bind_groups[0]);
+            }
+        });
+    }
+
+    /// Begins a pass drawing a fullscreen triangle onto the given view.
+    fn draw<'a>(
+        encoder: &'a mut CommandEncoder,
+        label: &str,
+        view: &TextureView,
+        load: LoadOp<Color>,
+    ) -> FullscreenPass<'a> {
+        FullscreenPass(encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some(&format!("PostProcessing::{label}_pass")),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view,
+                depth_slice: None,
+                resolve_target: None,
+                ops: Operations {
+                    load,
+                    store: StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        }))
+    }
+}
+
+/// A render pass drawing a single fullscreen triangle, see [`PostProcessing::draw`].
+struct FullscreenPass<'a>(RenderPass<'a>);
+
+impl FullscreenPass<'_> {
+    /// Sets the blend constant used by pipelines blending with [`BlendFactor::Constant`].
+    fn set_blend_constant(&mut self, color: Color) {
+        self.0.set_blend_constant(color);
+    }
+
+    /// Draws the fullscreen triangle with the given pipeline, reading the given bind group.
+    fn draw_with(mut self, pipeline: &RenderPipeline, bind_group: &BindGroup) {
+        self.0.set_pipeline(pipeline);
+        self.0.set_bind_group(0, bind_group, &[]);
+        self.0.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,81 @@
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
+
+use egui::epaint::{ImageData, ImageDelta};
+use image::RgbaImage;
+
+use crate::renderer::{
+    material::{MaterialData, MaterialId},
+    mesh::MeshData,
+    texture::{ColorSpace, TextureId},
+};
+
+/// Where a texture uploaded to the renderer came from.
+#[derive(Debug, Clone)]
+pub enum TextureSource {
+    /// An image uploaded directly.
+    Image {
+        /// The uploaded image.
+        image: RgbaImage,
+        /// How the colors of the image are encoded.
+        color_space: ColorSpace,
+    },
+    /// An image loaded from disk.
+    File {
+        /// The path of the image.
+        path: PathBuf,
+        /// How the colors of the image are encoded.
+        color_space: ColorSpace,
+    },
+}
+
+/// The CPU side copies of every resource uploaded to the renderer, kept so that they can be
+/// uploaded again if the device is lost.
+#[derive(Default)]
+pub struct ResourceSources {
+    /// Every uploaded mesh along with its material, by [`MeshId`](crate::renderer::mesh::MeshId).
+    pub meshes: Vec<(MeshData, MaterialId)>,
+    /// Every uploaded material along with the textures it refers to, by [`MaterialId`].
+    pub materials: Vec<(MaterialData, Vec<TextureId>)>,
+    /// Every uploaded texture, by [`TextureId`].
+    pub textures: Vec<TextureSource>,
+    /// The full contents of every live UI texture, with all partial updates applied.
+    pub ui_textures: HashMap<egui::TextureId, ImageDelta>,
+    /// The strip the color lookup table was created from, unless it is the identity.
+    pub color_lut: Option<RgbaImage>,
+}
+
+impl ResourceSources {
+    /// Records a change to a UI texture, applying partial updates to the stored texture.
+    pub fn update_ui_texture(&mut self, id: egui::TextureId, delta: &ImageDelta) {
+        let Some([x, y]) = delta.pos else {
+            self.ui_textures.insert(id, delta.clone());
+            return;
+        };
+
+        let Some(ImageDelta {
+            image: ImageData::Color(stored),
+            ..
+        }) = self.ui_textures.get_mut(&id)
+        else {
+            return;
+        };
+
+        let ImageData::Color(patch) = &delta.image;
+
+        let stored = Arc::make_mut(stored);
+        let [stored_width, _] = stored.size;
+        let [patch_width, patch_height] = patch.size;
+
+        for row in 0..patch_height {
+            let start = (y + row) * stored_width + x;
+
+            stored.pixels[start..start + patch_width]
+                .copy_from_slice(&patch.pixels[row * patch_width..(row + 1) * patch_width]);
+        }
+    }
+
+    /// Forgets a UI texture which has been freed.
+    pub fn free_ui_texture(&mut self, id: egui::TextureId) {
+        self.ui_textures.remove(&id);
+    }
+}
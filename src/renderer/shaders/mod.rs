@@ -0,0 +1,226 @@
+pub mod preprocessor;
+pub mod reflection;
+
+use std::{collections::HashMap, error::Error, fmt};
+
+use naga::valid::{Capabilities, ModuleInfo, ValidationFlags, Validator};
+use wgpu::{Device, ShaderModule, ShaderModuleDescriptor, ShaderSource};
+
+use crate::renderer::shaders::{
+    preprocessor::{Preprocessor, ProcessedShader, ShaderFiles},
+    reflection::ShaderReflection,
+};
+
+/// All compiled and hot reloadable shaders used in the application.
+#[derive(Clone)]
+pub struct Shaders {
+    /// The shader used for drawing meshes.
+    pub mesh_shader: ShaderModule,
+    /// The shader used for copying one texture onto another with a fullscreen triangle.
+    pub blit_shader: ShaderModule,
+    /// The shader used for tonemapping the HDR scene onto the target with a fullscreen triangle.
+    pub tonemap_shader: ShaderModule,
+    /// The shader used for extracting, blurring and compositing the bloom of the HDR scene.
+    pub bloom_shader: ShaderModule,
+    /// The shader holding every post-processing effect applied after tonemapping.
+    pub post_shader: ShaderModule,
+    /// The shader used for drawing particles as billboards facing the camera.
+    pub particle_shader: ShaderModule,
+    /// The compute shader used for spawning and moving particles.
+    pub particle_simulation_shader: ShaderModule,
+
+    /// The resources and inputs of every shader, reflected from their sources.
+    reflections: HashMap<ShaderKind, ShaderReflection>,
+}
+
+/// Identifies one of the [`Shaders`], along with the file in `assets/` it is compiled from.
+///
+/// Other files within `assets/` may only be included by shaders (see [`Preprocessor`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShaderKind {
+    /// See [`Shaders::mesh_shader`].
+    Mesh,
+    /// See [`Shaders::blit_shader`].
+    Blit,
+    /// See [`Shaders::tonemap_shader`].
+    Tonemap,
+    /// See [`Shaders::bloom_shader`].
+    Bloom,
+    /// See [`Shaders::post_shader`].
+    Post,
+    /// See [`Shaders::particle_shader`].
+    Particle,
+    /// See [`Shaders::particle_simulation_shader`].
+    ParticleSimulation,
+}
+
+/// An error produced while compiling a shader, formatted against its source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShaderError {
+    /// The shader which failed to compile.
+    pub kind: ShaderKind,
+    /// The human readable (multiline) description of the error.
+    pub message: String,
+}
+
+impl ShaderKind {
+    /// Every kind of shader.
+    pub const ALL: [Self; 7] = [
+        Self::Mesh,
+        Self::Blit,
+        Self::Tonemap,
+        Self::Bloom,
+        Self::Post,
+        Self::Particle,
+        Self::ParticleSimulation,
+    ];
+
+    /// Returns the name of the file (within `assets/`) the shader is compiled from.
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Self::Mesh => "mesh_shader.wgsl",
+            Self::Blit => "blit_shader.wgsl",
+            Self::Tonemap => "tonemap_shader.wgsl",
+            Self::Bloom => "bloom_shader.wgsl",
+            Self::Post => "post_shader.wgsl",
+            Self::Particle => "particle_shader.wgsl",
+            Self::ParticleSimulation => "particle_simulation_shader.wgsl",
+        }
+    }
+
+    /// Returns the kind of shader compiled from the file with the given name, if any.
+    pub fn from_file_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.file_name() == name)
+    }
+}
+
+impl Shaders {
+    /// Creates and compiles all shaders from their embedded sources.
+    pub fn new(device: &Device) -> Self {
+        let mut reflections = HashMap::new();
+
+        let mut compile = |kind: ShaderKind| {
+            let (shader, reflection) = Self::load(kind, &ShaderFiles::Embedded)
+                .unwrap_or_else(|e| panic!("invalid embedded shader: {e}"));
+
+            reflections.insert(kind, reflection);
+            Self::compile(device, kind, &shader)
+        };
+
+        Self {
+            mesh_shader: compile(ShaderKind::Mesh),
+            blit_shader: compile(ShaderKind::Blit),
+            tonemap_shader: compile(ShaderKind::Tonemap),
+            bloom_shader: compile(ShaderKind::Bloom),
+            post_shader: compile(ShaderKind::Post),
+            particle_shader: compile(ShaderKind::Particle),
+            particle_simulation_shader: compile(ShaderKind::ParticleSimulation),
+            reflections,
+        }
+    }
+
+    /// Returns the compiled module of the given shader.
+    pub fn get(&self, kind: ShaderKind) -> &ShaderModule {
+        match kind {
+            ShaderKind::Mesh => &self.mesh_shader,
+            ShaderKind::Blit => &self.blit_shader,
+            ShaderKind::Tonemap => &self.tonemap_shader,
+            ShaderKind::Bloom => &self.bloom_shader,
+            ShaderKind::Post => &self.post_shader,
+            ShaderKind::Particle => &self.particle_shader,
+            ShaderKind::ParticleSimulation => &self.particle_simulation_shader,
+        }
+    }
+
+    /// Returns the resources and inputs of the given shader.
+    pub fn reflection(&self, kind: ShaderKind) -> &ShaderReflection {
+        &self.reflections[&kind]
+    }
+
+    /// Recompiles the given shader from the given files, keeping the previous module if the new
+    /// source fails to validate.
+    pub fn reload(
+        &mut self,
+        device: &Device,
+        kind: ShaderKind,
+        files: &ShaderFiles,
+    ) -> Result<(), ShaderError> {
+        let (shader, reflection) = Self::load(kind, files)?;
+        let module = Self::compile(device, kind, &shader);
+
+        match kind {
+            ShaderKind::Mesh => self.mesh_shader = module,
+            ShaderKind::Blit => self.blit_shader = module,
+            ShaderKind::Tonemap => self.tonemap_shader = module,
+            ShaderKind::Bloom => self.bloom_shader = module,
+            ShaderKind::Post => self.post_shader = module,
+            ShaderKind::Particle => self.particle_shader = module,
+            ShaderKind::ParticleSimulation => self.particle_simulation_shader = module,
+        }
+
+        self.reflections.insert(kind, reflection);
+
+        Ok(())
+    }
+
+    /// Preprocesses, validates and reflects the given shader, without needing a device.
+    pub fn load(
+        kind: ShaderKind,
+        files: &ShaderFiles,
+    ) -> Result<(ProcessedShader, ShaderReflection), ShaderError> {
+        let error = |message| ShaderError { kind, message };
+
+        let shader = Preprocessor::new(files)
+            .process(kind.file_name())
+            .map_err(|e| error(e.to_string()))?;
+
+        let (module, info) = Self::validate(kind, &shader)?;
+        let reflection = ShaderReflection::new(&module, &info).map_err(error)?;
+
+        Ok((shader, reflection))
+    }
+
+    /// Parses and validates a preprocessed shader, with errors pointing at the original files.
+    pub fn validate(
+        kind: ShaderKind,
+        shader: &ProcessedShader,
+    ) -> Result<(naga::Module, ModuleInfo), ShaderError> {
+        let source = &shader.source;
+        let error = |message| ShaderError { kind, message };
+
+        let module = naga::front::wgsl::parse_str(source)
+            .map_err(|e| error(shader.describe_error(e.message(), e.location(source))))?;
+
+        let info = Validator::new(ValidationFlags::all(), Capabilities::all())
+            .validate(&module)
+            .map_err(|e| {
+                let mut message = e.as_inner().to_string();
+                let mut cause = e.as_inner().source();
+
+                while let Some(inner) = cause {
+                    message += &format!(": {inner}");
+                    cause = inner.source();
+                }
+
+                error(shader.describe_error(&message, e.location(source)))
+            })?;
+
+        Ok((module, info))
+    }
+
+    /// Compiles a preprocessed shader into a module.
+    fn compile(device: &Device, kind: ShaderKind, shader: &ProcessedShader) -> ShaderModule {
+        device.create_shader_module(ShaderModuleDescriptor {
+            label: Some(kind.file_name()),
+            source: ShaderSource::Wgsl(shader.source.as_str().into()),
+        })
+    }
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for ShaderError {}
@@ -0,0 +1,331 @@
+use std::{borrow::Cow, collections::HashSet, fmt, io, path::PathBuf, sync::Arc};
+
+/// Every file within `assets/` which can be read by the preprocessor, embedded at build time.
+const EMBEDDED_FILES: &[(&str, &str)] = &[
+    (
+        "bloom_shader.wgsl",
+        include_str!("../../../assets/bloom_shader.wgsl"),
+    ),
+    (
+        "blit_shader.wgsl",
+        include_str!("../../../assets/blit_shader.wgsl"),
+    ),
+    ("camera.wgsl", include_str!("../../../assets/camera.wgsl")),
+    ("color.wgsl", include_str!("../../../assets/color.wgsl")),
+    (
+        "fullscreen.wgsl",
+        include_str!("../../../assets/fullscreen.wgsl"),
+    ),
+    (
+        "mesh_shader.wgsl",
+        include_str!("../../../assets/mesh_shader.wgsl"),
+    ),
+    (
+        "particle.wgsl",
+        include_str!("../../../assets/particle.wgsl"),
+    ),
+    (
+        "particle_shader.wgsl",
+        include_str!("../../../assets/particle_shader.wgsl"),
+    ),
+    (
+        "particle_simulation_shader.wgsl",
+        include_str!("../../../assets/particle_simulation_shader.wgsl"),
+    ),
+    (
+        "post_shader.wgsl",
+        include_str!("../../../assets/post_shader.wgsl"),
+    ),
+    (
+        "tonemap_shader.wgsl",
+        include_str!("../../../assets/tonemap_shader.wgsl"),
+    ),
+];
+
+/// Where shader files (and the files they include) are read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShaderFiles {
+    /// The files of `assets/` as they were at build time.
+    Embedded,
+    /// The files within a directory on disk.
+    Directory(PathBuf),
+}
+
+/// Resolves the directives of a WGSL file, producing a single source ready to be compiled.
+///
+/// The supported directives are:
+/// - `#include "file.wgsl"`, which pastes in another file (only the first time it is included).
+/// - `#define NAME`, which enables a feature for the rest of the shader.
+/// - `#ifdef NAME`, `#ifndef NAME`, `#else` and `#endif`, which only keep lines for the enabled
+///   features.
+pub struct Preprocessor<'a> {
+    /// The files being read from.
+    files: &'a ShaderFiles,
+    /// The features enabled before processing starts.
+    defines: HashSet<String>,
+}
+
+/// A shader with all directives resolved, remembering where each of its lines came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessedShader {
+    /// The resulting WGSL source.
+    pub source: String,
+    /// The original location of every line of the `source`.
+    lines: Vec<SourceLocation>,
+}
+
+/// A line within one of the original shader files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    /// The name of the file.
+    pub file: Arc<str>,
+    /// The line number within the file (starting at 1).
+    pub line: usize,
+}
+
+/// An error produced by an invalid or missing directive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreprocessError {
+    /// Where the error occurred, unless the shader itself could not be read.
+    pub location: Option<SourceLocation>,
+    /// A description of the error.
+    pub message: String,
+}
+
+/// The state of a single expansion of a shader.
+struct Expansion<'a> {
+    /// The files being read from.
+    files: &'a ShaderFiles,
+    /// Every enabled feature.
+    defines: HashSet<String>,
+    /// The names of all files included so far.
+    included: HashSet<String>,
+    /// The shader produced so far.
+    output: ProcessedShader,
+}
+
+/// An `#ifdef` or `#ifndef` block which is still open.
+struct Conditional {
+    /// The location of the directive which opened the block.
+    location: SourceLocation,
+    /// Whether the lines of the current branch are kept.
+    active: bool,
+    /// Whether the `#else` branch has been reached.
+    in_else: bool,
+}
+
+impl ShaderFiles {
+    /// Reads the file with the given name.
+    pub fn read(&self, name: &str) -> io::Result<Cow<'static, str>> {
+        match self {
+            Self::Embedded => EMBEDDED_FILES
+                .iter()
+                .find(|(file, _)| *file == name)
+                .map(|(_, source)| Cow::Borrowed(*source))
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no such embedded file")),
+            Self::Directory(directory) => {
+                std::fs::read_to_string(directory.join(name)).map(Cow::Owned)
+            }
+        }
+    }
+}
+
+impl<'a> Preprocessor<'a> {
+    /// Creates a new [`Preprocessor`] reading shaders from the given files, with no features
+    /// enabled.
+    pub fn new(files: &'a ShaderFiles) -> Self {
+        Self {
+            files,
+            defines: HashSet::new(),
+        }
+    }
+
+    /// Enables a feature, as if every shader started with `#define NAME`.
+    pub fn define(mut self, name: impl Into<String>) -> Self {
+        self.defines.insert(name.into());
+        self
+    }
+
+    /// Reads the shader with the given name, resolving all of its directives.
+    pub fn process(&self, name: &str) -> Result<ProcessedShader, PreprocessError> {
+        let mut expansion = Expansion {
+            files: self.files,
+            defines: self.defines.clone(),
+            included: HashSet::new(),
+            output: ProcessedShader {
+                source: String::new(),
+                lines: Vec::new(),
+            },
+        };
+
+        expansion.include(name, None)?;
+
+        Ok(expansion.output)
+    }
+}
+
+impl Expansion<'_> {
+    /// Appends the lines of a file to the output, unless it has already been included.
+    fn include(
+        &mut self,
+        name: &str,
+        site: Option<&SourceLocation>,
+    ) -> Result<(), PreprocessError> {
+        if !self.included.insert(name.to_owned()) {
+            return Ok(());
+        }
+
+        let source = self.files.read(name).map_err(|e| PreprocessError {
+            location: site.cloned(),
+            message: format!("failed to read {name}: {e}"),
+        })?;
+
+        let file = Arc::<str>::from(name);
+        let mut conditionals = Vec::<Conditional>::new();
+
+        for (index, text) in source.lines().enumerate() {
+            let location = SourceLocation {
+                file: Arc::clone(&file),
+                line: index + 1,
+            };
+
+            let active = conditionals.iter().all(|conditional| conditional.active);
+
+            let Some(directive) = text.trim_start().strip_prefix('#') else {
+                if active {
+                    self.output.source.push_str(text);
+                    self.output.source.push('\n');
+                    self.output.lines.push(location);
+                }
+
+                continue;
+            };
+
+            let error = |message: String| PreprocessError {
+                location: Some(location.clone()),
+                message,
+            };
+
+            let (keyword, argument) = directive
+                .split_once(char::is_whitespace)
+                .map_or((directive, ""), |(keyword, argument)| {
+                    (keyword, argument.trim())
+                });
+
+            match keyword {
+                "include" => {
+                    let included = argument
+                        .strip_prefix('"')
+                        .and_then(|argument| argument.strip_suffix('"'))
+                        .filter(|path| !path.is_empty())
+                        .ok_or_else(|| {
+                            error(format!("expected a quoted path, found `{argument}`"))
+                        })?;
+
+                    if active {
+                        self.include(included, Some(&location))?;
+                    }
+                }
+                "define" => {
+                    let feature = parse_feature(argument).map_err(error)?;
+
+                    if active {
+                        self.defines.insert(feature.to_owned());
+                    }
+                }
+                "ifdef" | "ifndef" => {
+                    let feature = parse_feature(argument).map_err(error)?;
+
+                    conditionals.push(Conditional {
+                        active: self.defines.contains(feature) == (keyword == "ifdef"),
+                        location,
+                        in_else: false,
+                    });
+                }
+                "else" => match conditionals.last_mut() {
+                    Some(conditional) if !conditional.in_else => {
+                        conditional.active = !conditional.active;
+                        conditional.in_else = true;
+                    }
+                    Some(_) => return Err(error("duplicate `#else`".to_owned())),
+                    None => return Err(error("`#else` without a matching `#ifdef`".to_owned())),
+                },
+                "endif" => {
+                    if conditionals.pop().is_none() {
+                        return Err(error("`#endif` without a matching `#ifdef`".to_owned()));
+                    }
+                }
+                _ => return Err(error(format!("unknown directive `#{keyword}`"))),
+            }
+        }
+
+        if let Some(conditional) = conditionals.pop() {
+            return Err(PreprocessError {
+                location: Some(conditional.location),
+                message: "`#ifdef` is never closed by an `#endif`".to_owned(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses the name of the feature given to a directive.
+fn parse_feature(argument: &str) -> Result<&str, String> {
+    let valid = !argument.is_empty()
+        && argument
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    match valid {
+        true => Ok(argument),
+        false => Err(format!("expected a feature name, found `{argument}`")),
+    }
+}
+
+impl ProcessedShader {
+    /// Returns the original location of a line (starting at 1) of the processed `source`.
+    pub fn location(&self, line: usize) -> Option<&SourceLocation> {
+        self.lines.get(line.checked_sub(1)?)
+    }
+
+    /// Describes an error at the given location within the processed `source`, pointing at the
+    /// original file and line.
+    pub fn describe_error(&self, message: &str, location: Option<naga::SourceLocation>) -> String {
+        let Some(location) = location else {
+            return message.to_owned();
+        };
+
+        let line = location.line_number as usize;
+        let text = self
+            .source
+            .lines()
+            .nth(line.saturating_sub(1))
+            .unwrap_or("");
+
+        match self.location(line) {
+            Some(original) => format!(
+                "{original}:{}: {message}\n    | {text}",
+                location.line_position
+            ),
+            None => message.to_owned(),
+        }
+    }
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)
+    }
+}
+
+impl fmt::Display for PreprocessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.location {
+            Some(location) => write!(f, "{location}: {}", self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for PreprocessError {}
@@ -0,0 +1,310 @@
+use std::num::NonZeroU64;
+
+use naga::{
+    AddressSpace, Binding, ImageClass, ImageDimension, Module, Scalar, ScalarKind, ShaderStage,
+    StorageAccess, StorageFormat, TypeInner, VectorSize, valid::ModuleInfo,
+};
+use wgpu::*;
+
+/// The resources and inputs of a shader, reflected from its source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShaderReflection {
+    /// The entries of every bind group layout declared by the shader, by group index.
+    pub bind_groups: Vec<Vec<BindGroupLayoutEntry>>,
+    /// The attributes read by the vertex entry point (if there is one), by location, which the
+    /// hand-written vertex buffer layouts are checked against.
+    pub vertex_attributes: Vec<VertexAttribute>,
+    /// The name and workgroup size of every compute entry point.
+    pub workgroup_sizes: Vec<(String, [u32; 3])>,
+}
+
+impl ShaderReflection {
+    /// Reflects the bind groups, vertex inputs and workgroup sizes of a validated module.
+    ///
+    /// Bindings are only visible to the stages using them (or every stage if unused), and float
+    /// textures are always assumed to be filterable.
+    pub fn new(module: &Module, info: &ModuleInfo) -> Result<Self, String> {
+        let all_stages = module
+            .entry_points
+            .iter()
+            .fold(ShaderStages::NONE, |stages, entry_point| {
+                stages | stage(entry_point.stage)
+            });
+
+        let mut bind_groups = Vec::<Vec<BindGroupLayoutEntry>>::new();
+
+        for (handle, variable) in module.global_variables.iter() {
+            let Some(binding) = &variable.binding else {
+                continue;
+            };
+
+            let name = variable.name.as_deref().unwrap_or("<unnamed>");
+
+            let visibility = module
+                .entry_points
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| !info.get_entry_point(*index)[handle].is_empty())
+                .fold(ShaderStages::NONE, |stages, (_, entry_point)| {
+                    stages | stage(entry_point.stage)
+                });
+
+            let ty = binding_type(module, variable.space, variable.ty)
+                .map_err(|e| format!("cannot bind {name}: {e}"))?;
+
+            let group = binding.group as usize;
+
+            if bind_groups.len() <= group {
+                bind_groups.resize_with(group + 1, Vec::new);
+            }
+
+            bind_groups[group].push(BindGroupLayoutEntry {
+                binding: binding.binding,
+                visibility: match visibility {
+                    ShaderStages::NONE => all_stages,
+                    visibility => visibility,
+                },
+                ty,
+                count: None,
+            });
+        }
+
+        for entries in &mut bind_groups {
+            entries.sort_by_key(|entry| entry.binding);
+        }
+
+        let workgroup_sizes = module
+            .entry_points
+            .iter()
+            .filter(|entry_point| entry_point.stage == ShaderStage::Compute)
+            .map(|entry_point| (entry_point.name.clone(), entry_point.workgroup_size))
+            .collect();
+
+        Ok(Self {
+            bind_groups,
+            vertex_attributes: vertex_attributes(module)?,
+            workgroup_sizes,
+        })
+    }
+
+    /// Returns the workgroup size of the compute entry point with the given name, if there is
+    /// one.
+    pub fn workgroup_size(&self, entry_point: &str) -> Option<[u32; 3]> {
+        self.workgroup_sizes
+            .iter()
+            .find(|(name, _)| name == entry_point)
+            .map(|&(_, size)| size)
+    }
+
+    /// Checks that every attribute read by the shader is provided by a vertex buffer with the
+    /// given attributes.
+    pub fn check_vertex_attributes(&self, provided: &[VertexAttribute]) -> Result<(), String> {
+        for attribute in &self.vertex_attributes {
+            let location = attribute.shader_location;
+
+            match provided.iter().find(|p| p.shader_location == location) {
+                Some(p) if p.format == attribute.format => {}
+                Some(p) => {
+                    return Err(format!(
+                        "location {location} is read as {:?}, but the vertex buffer provides {:?}",
+                        attribute.format, p.format
+                    ));
+                }
+                None => {
+                    return Err(format!(
+                        "location {location} is read, but the vertex buffer doesn't provide it"
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the pipeline stage of a shader stage.
+fn stage(stage: ShaderStage) -> ShaderStages {
+    match stage {
+        ShaderStage::Vertex => ShaderStages::VERTEX,
+        ShaderStage::Fragment => ShaderStages::FRAGMENT,
+        ShaderStage::Compute => ShaderStages::COMPUTE,
+        _ => ShaderStages::NONE,
+    }
+}
+
+/// Returns the type of binding needed by a global variable.
+fn binding_type(
+    module: &Module,
+    space: AddressSpace,
+    ty: naga::Handle<naga::Type>,
+) -> Result<BindingType, String> {
+    let inner = &module.types[ty].inner;
+
+    let buffer = |ty: BufferBindingType| BindingType::Buffer {
+        ty,
+        has_dynamic_offset: false,
+        min_binding_size: NonZeroU64::new(inner.size(module.to_ctx()) as u64),
+    };
+
+    Ok(match (space, inner) {
+        (AddressSpace::Uniform, _) => buffer(BufferBindingType::Uniform),
+        (AddressSpace::Storage { access }, _) => buffer(BufferBindingType::Storage {
+            read_only: !access.contains(StorageAccess::STORE),
+        }),
+        (AddressSpace::Handle, TypeInner::Sampler { comparison }) => {
+            BindingType::Sampler(match comparison {
+                true => SamplerBindingType::Comparison,
+                false => SamplerBindingType::Filtering,
+            })
+        }
+        (
+            AddressSpace::Handle,
+            TypeInner::Image {
+                dim,
+                arrayed,
+                class,
+            },
+        ) => {
+            let view_dimension = view_dimension(*dim, *arrayed);
+
+            match *class {
+                ImageClass::Sampled { kind, multi } => BindingType::Texture {
+                    sample_type: match kind {
+                        ScalarKind::Float => TextureSampleType::Float { filterable: true },
+                        ScalarKind::Sint => TextureSampleType::Sint,
+                        ScalarKind::Uint => TextureSampleType::Uint,
+                        kind => return Err(format!("unsupported texture type {kind:?}")),
+                    },
+                    view_dimension,
+                    multisampled: multi,
+                },
+                ImageClass::Depth { multi } => BindingType::Texture {
+                    sample_type: TextureSampleType::Depth,
+                    view_dimension,
+                    multisampled: multi,
+                },
+                ImageClass::Storage { format, access } => BindingType::StorageTexture {
+                    access: match (
+                        access.contains(StorageAccess::LOAD),
+                        access.contains(StorageAccess::STORE),
+                    ) {
+                        (true, true) => StorageTextureAccess::ReadWrite,
+                        (true, false) => StorageTextureAccess::ReadOnly,
+                        _ => StorageTextureAccess::WriteOnly,
+                    },
+                    format: storage_format(format)?,
+                    view_dimension,
+                },
+                ref class => return Err(format!("unsupported image class {class:?}")),
+            }
+        }
+        (space, inner) => return Err(format!("unsupported resource {inner:?} in {space:?}")),
+    })
+}
+
+/// Returns the view dimension of a texture with the given dimension.
+fn view_dimension(dim: ImageDimension, arrayed: bool) -> TextureViewDimension {
+    match (dim, arrayed) {
+        (ImageDimension::D1, _) => TextureViewDimension::D1,
+        (ImageDimension::D2, false) => TextureViewDimension::D2,
+        (ImageDimension::D2, true) => TextureViewDimension::D2Array,
+        (ImageDimension::D3, _) => TextureViewDimension::D3,
+        (ImageDimension::Cube, false) => TextureViewDimension::Cube,
+        (ImageDimension::Cube, true) => TextureViewDimension::CubeArray,
+    }
+}
+
+/// Returns the texture format of a storage texture, for the commonly supported formats.
+fn storage_format(format: StorageFormat) -> Result<TextureFormat, String> {
+    Ok(match format {
+        StorageFormat::R32Uint => TextureFormat::R32Uint,
+        StorageFormat::R32Sint => TextureFormat::R32Sint,
+        StorageFormat::R32Float => TextureFormat::R32Float,
+        StorageFormat::Rgba8Unorm => TextureFormat::Rgba8Unorm,
+        StorageFormat::Rgba8Snorm => TextureFormat::Rgba8Snorm,
+        StorageFormat::Rgba8Uint => TextureFormat::Rgba8Uint,
+        StorageFormat::Rgba8Sint => TextureFormat::Rgba8Sint,
+        StorageFormat::Bgra8Unorm => TextureFormat::Bgra8Unorm,
+        StorageFormat::Rgba16Uint => TextureFormat::Rgba16Uint,
+        StorageFormat::Rgba16Sint => TextureFormat::Rgba16Sint,
+        StorageFormat::Rgba16Float => TextureFormat::Rgba16Float,
+        StorageFormat::Rgba32Uint => TextureFormat::Rgba32Uint,
+        StorageFormat::Rgba32Sint => TextureFormat::Rgba32Sint,
+        StorageFormat::Rgba32Float => TextureFormat::Rgba32Float,
+        format => return Err(format!("unsupported storage texture format {format:?}")),
+    })
+}
+
+/// Returns the attributes read by the first vertex entry point of a module, by location.
+fn vertex_attributes(module: &Module) -> Result<Vec<VertexAttribute>, String> {
+    let Some(entry_point) = module
+        .entry_points
+        .iter()
+        .find(|entry_point| entry_point.stage == ShaderStage::Vertex)
+    else {
+        return Ok(Vec::new());
+    };
+
+    // inputs are either arguments or the members of struct arguments
+    let mut inputs = Vec::new();
+
+    for argument in &entry_point.function.arguments {
+        match &module.types[argument.ty].inner {
+            TypeInner::Struct { members, .. } => {
+                inputs.extend(members.iter().map(|member| (&member.binding, member.ty)))
+            }
+            _ => inputs.push((&argument.binding, argument.ty)),
+        }
+    }
+
+    let mut attributes = Vec::new();
+    let mut offset = 0;
+
+    for (binding, ty) in inputs {
+        let Some(Binding::Location { location, .. }) = binding else {
+            continue;
+        };
+
+        let format = vertex_format(&module.types[ty].inner)
+            .ok_or_else(|| format!("unsupported vertex input type at location {location}"))?;
+
+        attributes.push(VertexAttribute {
+            format,
+            offset,
+            shader_location: *location,
+        });
+
+        offset += format.size();
+    }
+
+    attributes.sort_by_key(|attribute| attribute.shader_location);
+
+    Ok(attributes)
+}
+
+/// Returns the vertex format read into the given (32 bit scalar or vector) type.
+fn vertex_format(inner: &TypeInner) -> Option<VertexFormat> {
+    let (size, scalar) = match *inner {
+        TypeInner::Scalar(scalar) => (None, scalar),
+        TypeInner::Vector { size, scalar } => (Some(size), scalar),
+        _ => return None,
+    };
+
+    use VectorSize::*;
+
+    Some(match (scalar, size) {
+        (Scalar::F32, None) => VertexFormat::Float32,
+        (Scalar::F32, Some(Bi)) => VertexFormat::Float32x2,
+        (Scalar::F32, Some(Tri)) => VertexFormat::Float32x3,
+        (Scalar::F32, Some(Quad)) => VertexFormat::Float32x4,
+        (Scalar::U32, None) => VertexFormat::Uint32,
+        (Scalar::U32, Some(Bi)) => VertexFormat::Uint32x2,
+        (Scalar::U32, Some(Tri)) => VertexFormat::Uint32x3,
+        (Scalar::U32, Some(Quad)) => VertexFormat::Uint32x4,
+        (Scalar::I32, None) => VertexFormat::Sint32,
+        (Scalar::I32, Some(Bi)) => VertexFormat::Sint32x2,
+        (Scalar::I32, Some(Tri)) => VertexFormat::Sint32x3,
+        (Scalar::I32, Some(Quad)) => VertexFormat::Sint32x4,
+        _ => return None,
+    })
+}
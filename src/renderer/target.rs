@@ -0,0 +1,146 @@
+use std::sync::Arc;
+
+use wgpu::*;
+use winit::window::Window;
+
+/// The destination of everything drawn by the renderer.
+pub enum RenderTarget {
+    /// Renders onto the surface of a window, presenting each frame to the screen.
+    Window {
+        /// The window being rendered onto.
+        window: Arc<Window>,
+        /// The primary surface texture being rendered onto.
+        surface: Surface<'static>,
+    },
+
+    /// Renders into a texture which is never presented (useful without a display).
+    Offscreen {
+        /// The texture being rendered into.
+        texture: Texture,
+    },
+}
+
+/// Counts how often acquiring a frame from a [`RenderTarget`] has failed, by reason.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SurfaceErrorCounts {
+    /// The number of frames skipped because the surface took too long to provide a texture.
+    pub timeout: u32,
+    /// The number of times the surface no longer matched the window and was reconfigured.
+    pub outdated: u32,
+    /// The number of times the surface was lost and reconfigured.
+    pub lost: u32,
+    /// The number of frames skipped due to any other error.
+    pub other: u32,
+}
+
+/// A single frame acquired from a [`RenderTarget`], ready to be rendered into.
+pub struct Frame {
+    /// The texture of the swapchain, if this frame needs to be presented.
+    surface_texture: Option<SurfaceTexture>,
+
+    /// The texture being rendered into.
+    pub texture: Texture,
+    /// A view into the whole `texture`.
+    pub view: TextureView,
+}
+
+impl RenderTarget {
+    /// Creates a new offscreen target matching the given configuration.
+    pub fn offscreen(device: &Device, config: &SurfaceConfiguration) -> Self {
+        Self::Offscreen {
+            texture: Self::create_offscreen_texture(device, config),
+        }
+    }
+
+    /// Chooses the format of a surface supporting the given formats, using the preferred format
+    /// if it is supported, otherwise the first sRGB format (or the first format if there are
+    /// none).
+    pub fn choose_format(
+        supported: &[TextureFormat],
+        preference: Option<TextureFormat>,
+    ) -> Option<TextureFormat> {
+        preference
+            .filter(|format| supported.contains(format))
+            .or_else(|| supported.iter().copied().find(TextureFormat::is_srgb))
+            .or_else(|| supported.first().copied())
+    }
+
+    /// Reconfigures the target to match the given configuration (usually after a resize).
+    pub fn configure(&mut self, device: &Device, config: &SurfaceConfiguration) {
+        match self {
+            Self::Window { surface, .. } => surface.configure(device, config),
+            Self::Offscreen { texture } => {
+                *texture = Self::create_offscreen_texture(device, config);
+            }
+        }
+    }
+
+    /// Acquires the next [`Frame`] to render into (which only fails for window targets).
+    pub fn acquire(&self) -> Result<Frame, SurfaceError> {
+        let (surface_texture, texture) = match self {
+            Self::Window { surface, .. } => {
+                let output = surface.get_current_texture()?;
+                let texture = output.texture.clone();
+
+                (Some(output), texture)
+            }
+            Self::Offscreen { texture } => (None, texture.clone()),
+        };
+
+        let view = texture.create_view(&TextureViewDescriptor::default());
+
+        Ok(Frame {
+            surface_texture,
+            texture,
+            view,
+        })
+    }
+
+    /// Returns the window being rendered onto, if any.
+    pub fn window(&self) -> Option<&Arc<Window>> {
+        match self {
+            Self::Window { window, .. } => Some(window),
+            Self::Offscreen { .. } => None,
+        }
+    }
+
+    /// Returns the ratio of physical pixels to logical points of the target.
+    pub fn scale_factor(&self) -> f32 {
+        self.window()
+            .map_or(1.0, |window| window.scale_factor() as f32)
+    }
+
+    /// Creates the texture backing an offscreen target.
+    fn create_offscreen_texture(device: &Device, config: &SurfaceConfiguration) -> Texture {
+        device.create_texture(&TextureDescriptor {
+            label: Some("RenderTarget::offscreen_texture"),
+            size: Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: config.format,
+            usage: config.usage,
+            view_formats: &config.view_formats,
+        })
+    }
+}
+
+impl SurfaceErrorCounts {
+    /// Returns the total number of failures.
+    pub fn total(&self) -> u32 {
+        self.timeout + self.outdated + self.lost + self.other
+    }
+}
+
+impl Frame {
+    /// Presents the frame to the screen (a no-op for offscreen targets).
+    pub fn present(self) {
+        if let Some(surface_texture) = self.surface_texture {
+            surface_texture.present();
+        }
+    }
+}
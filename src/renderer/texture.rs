@@ -0,0 +1,332 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::Context;
+use half::f16;
+use image::{DynamicImage, Rgba32FImage, RgbaImage};
+use wgpu::*;
+
+use crate::renderer::pipelines::Pipelines;
+
+/// How the color channels of an image are encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColorSpace {
+    /// Colors are gamma encoded, as with most color (albedo and emissive) textures.
+    Srgb,
+    /// Values are stored as is, as with normal maps and other non-color data.
+    Linear,
+}
+
+/// Identifies a texture which has been uploaded to the renderer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextureId(pub(crate) usize);
+
+/// A sampled texture created from an image, with a full chain of mipmaps.
+pub struct ImageTexture {
+    /// The underlying texture.
+    pub texture: Texture,
+    /// A view into all mip levels of the `texture`.
+    pub view: TextureView,
+}
+
+/// Caches samplers by their descriptor, so that equal samplers are only created once.
+#[derive(Default)]
+pub struct SamplerCache {
+    /// All samplers created so far.
+    samplers: HashMap<SamplerKey, Sampler>,
+}
+
+/// The hashable parts of a [`SamplerDescriptor`] (everything but the label).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct SamplerKey {
+    address_modes: [AddressMode; 3],
+    filters: [FilterMode; 3],
+    lod_clamp: [u32; 2],
+    compare: Option<CompareFunction>,
+    anisotropy_clamp: u16,
+    border_color: Option<SamplerBorderColor>,
+}
+
+impl TextureId {
+    /// A single opaque white pixel, used in place of missing textures.
+    pub const WHITE: Self = Self(0);
+}
+
+impl ImageTexture {
+    /// Returns the number of mip levels in a full chain for a texture of the given size.
+    pub fn mip_level_count(width: u32, height: u32) -> u32 {
+        u32::BITS - width.max(height).max(1).leading_zeros()
+    }
+
+    /// Loads and decodes an image from disk (PNG, JPEG or HDR), uploading it into a new
+    /// [`ImageTexture`].
+    ///
+    /// High dynamic range images are always linear, ignoring the given color space.
+    pub fn load(
+        device: &Device,
+        queue: &Queue,
+        pipelines: &Pipelines,
+        path: impl AsRef<Path>,
+        color_space: ColorSpace,
+    ) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let label = path.display().to_string();
+
+        let image = image::open(path)
+            .with_context(|| format!("failed to load texture {}", path.display()))?;
+
+        Ok(match image {
+            DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_) => {
+                Self::from_rgba32f(device, queue, pipelines, &label, &image.into_rgba32f())
+            }
+            image => Self::from_rgba8(
+                device,
+                queue,
+                pipelines,
+                &label,
+                &image.into_rgba8(),
+                color_space,
+            ),
+        })
+    }
+
+    /// Uploads an 8 bit image into a new [`ImageTexture`].
+    pub fn from_rgba8(
+        device: &Device,
+        queue: &Queue,
+        pipelines: &Pipelines,
+        label: &str,
+        image: &RgbaImage,
+        color_space: ColorSpace,
+    ) -> Self {
+        let format = match color_space {
+            ColorSpace::Srgb => TextureFormat::Rgba8UnormSrgb,
+            ColorSpace::Linear => TextureFormat::Rgba8Unorm,
+        };
+
+        Self::new(
+            device,
+            queue,
+            pipelines,
+            label,
+            format,
+            image.dimensions(),
+            image,
+        )
+    }
+
+    /// Uploads a (linear) floating point image into a new [`ImageTexture`], stored with half
+    /// precision so that it can be filtered.
+    pub fn from_rgba32f(
+        device: &Device,
+        queue: &Queue,
+        pipelines: &Pipelines,
+        label: &str,
+        image: &Rgba32FImage,
+    ) -> Self {
+        let pixels = image
+            .iter()
+            .map(|&channel| f16::from_f32(channel).to_bits())
+            .collect::<Vec<_>>();
+
+        Self::new(
+            device,
+            queue,
+            pipelines,
+            label,
+            TextureFormat::Rgba16Float,
+            image.dimensions(),
+            bytemuck::cast_slice(&pixels),
+        )
+    }
+
+    /// Creates the texture, uploads the given pixels into its first mip level and generates the
+    /// rest.
+    fn new(
+        device: &Device,
+        queue: &Queue,
+        pipelines: &Pipelines,
+        label: &str,
+        format: TextureFormat,
+        (width, height): (u32, u32),
+        pixels: &[u8],
+    ) -> Self {
+        let size = Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some(label),
+            size,
+            mip_level_count: Self::mip_level_count(width, height),
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+
+        let bytes_per_pixel = format.block_copy_size(None).unwrap();
+
+        queue.write_texture(
+            texture.as_image_copy(),
+            pixels,
+            TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(width * bytes_per_pixel),
+                rows_per_image: Some(height),
+            },
+            size,
+        );
+
+        Self::generate_mipmaps(device, queue, pipelines, &texture);
+
+        let view = texture.create_view(&TextureViewDescriptor::default());
+
+        Self { texture, view }
+    }
+
+    /// Fills every mip level after the first by repeatedly downsampling the previous level.
+    fn generate_mipmaps(device: &Device, queue: &Queue, pipelines: &Pipelines, texture: &Texture) {
+        if texture.mip_level_count() <= 1 {
+            return;
+        }
+
+        let Some(pipeline) = pipelines.mipmap_pipeline(texture.format()) else {
+            log::warn!("cannot generate mipmaps for {:?}", texture.format());
+            return;
+        };
+
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("ImageTexture::mipmap_sampler"),
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let views = (0..texture.mip_level_count())
+            .map(|level| {
+                texture.create_view(&TextureViewDescriptor {
+                    base_mip_level: level,
+                    mip_level_count: Some(1),
+                    ..Default::default()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("ImageTexture::mipmap_encoder"),
+        });
+
+        for pair in views.windows(2) {
+            let [source, target] = pair else {
+                unreachable!()
+            };
+
+            let bind_group = device.create_bind_group(&BindGroupDescriptor {
+                label: Some("ImageTexture::mipmap_bind_group"),
+                layout: &pipelines.texture_bind_group_layout,
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: BindingResource::TextureView(source),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::Sampler(&sampler),
+                    },
+                ],
+            });
+
+            let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("ImageTexture::mipmap_pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: target,
+                    depth_slice: None,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(Color::TRANSPARENT),
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.draw(0..3, 0..1);
+        }
+
+        queue.submit([encoder.finish()]);
+    }
+}
+
+impl SamplerCache {
+    /// Returns a sampler matching the given descriptor, creating it if it doesn't exist yet.
+    pub fn get(&mut self, device: &Device, descriptor: &SamplerDescriptor) -> Sampler {
+        self.samplers
+            .entry(SamplerKey::from(descriptor))
+            .or_insert_with(|| device.create_sampler(descriptor))
+            .clone()
+    }
+
+    /// Returns the sampler used for material textures (trilinear filtering, repeating).
+    pub fn material_sampler(&mut self, device: &Device) -> Sampler {
+        self.get(
+            device,
+            &SamplerDescriptor {
+                label: Some("SamplerCache::material_sampler"),
+                address_mode_u: AddressMode::Repeat,
+                address_mode_v: AddressMode::Repeat,
+                address_mode_w: AddressMode::Repeat,
+                mag_filter: FilterMode::Linear,
+                min_filter: FilterMode::Linear,
+                mipmap_filter: FilterMode::Linear,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Returns the sampler used by fullscreen passes reading a whole texture (bilinear filtering,
+    /// clamped to the edges).
+    pub fn screen_sampler(&mut self, device: &Device) -> Sampler {
+        self.get(
+            device,
+            &SamplerDescriptor {
+                label: Some("SamplerCache::screen_sampler"),
+                mag_filter: FilterMode::Linear,
+                min_filter: FilterMode::Linear,
+                ..Default::default()
+            },
+        )
+    }
+}
+
+impl From<&SamplerDescriptor<'_>> for SamplerKey {
+    fn from(descriptor: &SamplerDescriptor) -> Self {
+        Self {
+            address_modes: [
+                descriptor.address_mode_u,
+                descriptor.address_mode_v,
+                descriptor.address_mode_w,
+            ],
+            filters: [
+                descriptor.mag_filter,
+                descriptor.min_filter,
+                descriptor.mipmap_filter,
+            ],
+            lod_clamp: [
+                descriptor.lod_min_clamp.to_bits(),
+                descriptor.lod_max_clamp.to_bits(),
+            ],
+            compare: descriptor.compare,
+            anisotropy_clamp: descriptor.anisotropy_clamp,
+            border_color: descriptor.border_color,
+        }
+    }
+}
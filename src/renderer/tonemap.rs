@@ -0,0 +1,169 @@
+use bytemuck::{Pod, Zeroable};
+use wgpu::*;
+
+use crate::renderer::pipelines::Pipelines;
+
+/// The curve mapping the unbounded colors of the HDR scene onto the displayable range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TonemapOperator {
+    /// A fit of the filmic curve of the Academy Color Encoding System, with strong contrast.
+    #[default]
+    Aces,
+    /// The simple Reinhard curve, which desaturates bright colors the least.
+    Reinhard,
+    /// The AgX curve, which gracefully desaturates very bright colors towards white.
+    AgX,
+}
+
+/// Controls how the HDR scene is mapped onto the target.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TonemapSettings {
+    /// The curve used to map colors.
+    pub operator: TonemapOperator,
+    /// The exposure in stops, each of which doubles the brightness of the scene.
+    pub exposure: f32,
+}
+
+/// The layout of the tonemapping uniform buffer, matching `Tonemap` in the tonemap shader.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+struct TonemapUniform {
+    exposure: f32,
+    operator: u32,
+    encode_srgb: u32,
+}
+
+/// The HDR texture the scene is rendered into, along with the resources used to tonemap it onto
+/// the target.
+pub struct HdrTarget {
+    /// The texture holding the linear HDR colors of the scene.
+    pub texture: Texture,
+    /// A view into the whole `texture`.
+    pub view: TextureView,
+    /// The uniform buffer holding the current [`TonemapSettings`].
+    buffer: Buffer,
+    /// The bind group holding the `view`, its sampler and the `buffer`.
+    bind_group: BindGroup,
+}
+
+impl TonemapOperator {
+    /// Every tonemapping operator.
+    pub const ALL: [Self; 3] = [Self::Aces, Self::Reinhard, Self::AgX];
+
+    /// Returns the human readable name of the operator.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Aces => "ACES",
+            Self::Reinhard => "Reinhard",
+            Self::AgX => "AgX",
+        }
+    }
+}
+
+impl Default for TonemapSettings {
+    fn default() -> Self {
+        Self {
+            operator: TonemapOperator::default(),
+            exposure: 0.0,
+        }
+    }
+}
+
+impl HdrTarget {
+    /// The format of the HDR texture, which keeps colors beyond 1.0 while staying filterable.
+    pub const FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+    /// Creates a new [`HdrTarget`] of the given size, sampled with the given sampler.
+    pub fn new(
+        device: &Device,
+        pipelines: &Pipelines,
+        sampler: &Sampler,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("HdrTarget::texture"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: Self::FORMAT,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&TextureViewDescriptor::default());
+
+        let buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("HdrTarget::buffer"),
+            size: size_of::<TonemapUniform>() as _,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("HdrTarget::bind_group"),
+            layout: &pipelines.tonemap_bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(&view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(sampler),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        Self {
+            texture,
+            view,
+            buffer,
+            bind_group,
+        }
+    }
+
+    /// Uploads the settings used by the next tonemapping pass onto a target of the given format.
+    pub fn write_settings(&self, queue: &Queue, settings: TonemapSettings, format: TextureFormat) {
+        let uniform = TonemapUniform {
+            exposure: settings.exposure.exp2(),
+            operator: settings.operator as u32,
+            // sRGB targets encode the colors written into them automatically
+            encode_srgb: !format.is_srgb() as u32,
+        };
+
+        queue.write_buffer(&self.buffer, 0, bytemuck::bytes_of(&uniform));
+    }
+
+    /// Records a pass tonemapping the HDR texture onto the given view.
+    pub fn tonemap(&self, encoder: &mut CommandEncoder, pipelines: &Pipelines, view: &TextureView) {
+        let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("HdrTarget::tonemap_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view,
+                depth_slice: None,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(Color::BLACK),
+                    store: StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        pass.set_pipeline(&pipelines.tonemap_pipeline);
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+}
@@ -18,6 +18,7 @@ pub struct FrameTimer {
 
 impl FrameTimer {
     /// Creates a new [`FrameTimer`].
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
         Self {
             last_frame: Instant::now(),
@@ -28,6 +29,16 @@ impl FrameTimer {
         }
     }
 
+    /// Restarts timing after a pause, so that the time spent paused isn't counted as one long
+    /// frame.
+    pub fn resume(&mut self) {
+        let now = Instant::now();
+
+        self.last_frame = now;
+        self.last_second = now;
+        self.frames_accumulated = 0;
+    }
+
     /// Advances the timer by one frame.
     pub fn tick(&mut self) {
         let now = Instant::now();
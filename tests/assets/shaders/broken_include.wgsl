@@ -0,0 +1,3 @@
+fn unknown() -> f32 {
+    return missing_value;
+}
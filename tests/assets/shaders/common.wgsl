@@ -0,0 +1 @@
+const SCALE: f32 = 2.0;
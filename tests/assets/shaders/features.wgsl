@@ -0,0 +1,12 @@
+#ifdef DOUBLE
+const FACTOR: f32 = 2.0;
+#else
+const FACTOR: f32 = 1.0;
+#endif
+
+#define NEGATE
+#ifndef NEGATE
+const SIGN: f32 = 1.0;
+#else
+const SIGN: f32 = -1.0;
+#endif
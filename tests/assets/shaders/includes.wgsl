@@ -0,0 +1,6 @@
+#include "common.wgsl"
+#include "common.wgsl"
+
+fn scaled(x: f32) -> f32 {
+    return x * SCALE;
+}
@@ -0,0 +1,3 @@
+#include "common.wgsl"
+
+#include "broken_include.wgsl"
@@ -0,0 +1,2 @@
+const A: f32 = 1.0;
+#include "does_not_exist.wgsl"
@@ -0,0 +1,3 @@
+const A: f32 = 1.0;
+#ifdef FEATURE
+const B: f32 = 1.0;
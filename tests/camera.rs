@@ -0,0 +1,145 @@
+//! Tests for the math of the first person [`Camera`].
+
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use glam::{Vec3, Vec4, vec3};
+use gpu_template::renderer::camera::Camera;
+use winit::{dpi::PhysicalSize, keyboard::KeyCode};
+
+/// The largest error allowed between floating point results.
+const EPSILON: f32 = 1e-5;
+
+/// Returns a camera at the origin looking down -Z, with a square aspect ratio.
+fn camera() -> Camera {
+    Camera {
+        position: Vec3::ZERO,
+        yaw: 0.0,
+        pitch: 0.0,
+        fov: 90.0f32.to_radians(),
+        aspect_ratio: 1.0,
+        movement_sensitivity: 1.0,
+        mouse_sensitivity: 1.0,
+    }
+}
+
+/// Projects a world space point into normalized device coordinates.
+fn project(camera: &Camera, point: Vec3) -> Vec3 {
+    let clip = camera.view_projection() * Vec4::from((point, 1.0));
+    clip.truncate() / clip.w
+}
+
+#[test]
+fn resize_sets_aspect_ratio() {
+    let mut camera = camera();
+
+    camera.resize(PhysicalSize::new(1920, 1080));
+
+    assert!((camera.aspect_ratio - 16.0 / 9.0).abs() < EPSILON);
+}
+
+#[test]
+fn zero_sizes_keep_the_previous_aspect_ratio() {
+    let mut camera = camera();
+    camera.resize(PhysicalSize::new(800, 400));
+
+    for size in [(0, 0), (0, 400), (800, 0)] {
+        camera.resize(PhysicalSize::new(size.0, size.1));
+
+        assert_eq!(camera.aspect_ratio, 2.0);
+        assert!(camera.view_projection().is_finite());
+    }
+}
+
+#[test]
+fn forward_follows_yaw_and_pitch() {
+    let mut camera = camera();
+    assert!(camera.forward().abs_diff_eq(Vec3::NEG_Z, EPSILON));
+
+    camera.yaw = FRAC_PI_2;
+    assert!(camera.forward().abs_diff_eq(Vec3::X, EPSILON));
+
+    camera.yaw = PI;
+    assert!(camera.forward().abs_diff_eq(Vec3::Z, EPSILON));
+
+    camera.yaw = 0.0;
+    camera.pitch = FRAC_PI_2 / 2.0;
+    assert!(
+        camera
+            .forward()
+            .abs_diff_eq(vec3(0.0, 1.0, -1.0).normalize(), EPSILON)
+    );
+}
+
+#[test]
+fn right_and_up_form_a_basis_with_forward() {
+    let mut camera = camera();
+    assert!(camera.right().abs_diff_eq(Vec3::X, EPSILON));
+    assert!(camera.up().abs_diff_eq(Vec3::Y, EPSILON));
+
+    // looking straight up keeps a well defined basis
+    for (yaw, pitch) in [(0.3, 0.7), (2.0, -1.2), (-1.0, FRAC_PI_2)] {
+        camera.yaw = yaw;
+        camera.pitch = pitch;
+
+        let (forward, right, up) = (camera.forward(), camera.right(), camera.up());
+
+        assert!((right.length() - 1.0).abs() < EPSILON);
+        assert!((up.length() - 1.0).abs() < EPSILON);
+        assert!(right.dot(forward).abs() < EPSILON);
+        assert!(up.dot(forward).abs() < EPSILON);
+        assert!(right.y.abs() < EPSILON);
+    }
+}
+
+#[test]
+fn pitch_is_clamped() {
+    let mut camera = camera();
+
+    camera.update_orientation((0.0, -10.0));
+    assert_eq!(camera.pitch, FRAC_PI_2);
+
+    camera.update_orientation((0.0, 20.0));
+    assert_eq!(camera.pitch, -FRAC_PI_2);
+}
+
+#[test]
+fn depth_is_reversed() {
+    let camera = camera();
+
+    // the near plane is at 0.1 units, and depth approaches zero towards infinity
+    let near = project(&camera, vec3(0.0, 0.0, -0.1));
+    let middle = project(&camera, vec3(0.0, 0.0, -10.0));
+    let far = project(&camera, vec3(0.0, 0.0, -1.0e6));
+
+    assert!(near.abs_diff_eq(vec3(0.0, 0.0, 1.0), EPSILON));
+    assert!(middle.z > far.z && middle.z < near.z);
+    assert!(far.z >= 0.0 && far.z < EPSILON);
+}
+
+#[test]
+fn projection_respects_aspect_ratio() {
+    let mut camera = camera();
+    camera.resize(PhysicalSize::new(200, 100));
+
+    // with a 90 degree vertical field of view, the top edge is as far up as the point is away
+    let top = project(&camera, vec3(0.0, 1.0, -1.0));
+    let right = project(&camera, vec3(2.0, 0.0, -1.0));
+
+    assert!((top.y - 1.0).abs() < EPSILON);
+    assert!((right.x - 1.0).abs() < EPSILON);
+}
+
+#[test]
+fn movement_is_normalized() {
+    let mut camera = camera();
+
+    let held = [KeyCode::KeyW, KeyCode::KeyD];
+    camera.update_position(|key| held.contains(key), 1.0);
+
+    assert!((camera.position.length() - 1.0).abs() < EPSILON);
+    assert!(
+        camera
+            .position
+            .abs_diff_eq(vec3(1.0, 0.0, -1.0).normalize(), EPSILON)
+    );
+}
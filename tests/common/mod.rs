@@ -0,0 +1,76 @@
+//! Fixtures shared by every test suite rendering with a headless [`Renderer`].
+
+// every suite only uses some of the fixtures
+#![allow(dead_code)]
+
+use glam::vec3;
+use gpu_template::renderer::{
+    Renderer, camera::Camera, capture::FrameCapture, config::RendererConfig,
+};
+use winit::dpi::PhysicalSize;
+
+/// Returns the configuration every headless renderer starts from.
+pub fn test_config() -> RendererConfig {
+    RendererConfig::headless()
+}
+
+/// The environment variable allowing tests to be skipped on machines without a usable adapter.
+pub const SKIP_WITHOUT_ADAPTER: &str = "GOLDEN_SKIP_WITHOUT_ADAPTER";
+
+/// Creates a headless renderer of the given size with the given configuration.
+///
+/// Panics if the machine has no usable adapter, unless [`SKIP_WITHOUT_ADAPTER`] is set, in which
+/// case `None` is returned and the test should be skipped.
+pub fn headless_renderer_with(size: PhysicalSize<u32>, config: RendererConfig) -> Option<Renderer> {
+    match pollster::block_on(Renderer::new_headless(size, config)) {
+        Ok(renderer) => Some(renderer),
+        Err(e) if std::env::var_os(SKIP_WITHOUT_ADAPTER).is_some() => {
+            eprintln!("skipping test, no adapter available: {e:#}");
+            None
+        }
+        Err(e) => panic!(
+            "no adapter available ({e:#}), run with {SKIP_WITHOUT_ADAPTER}=1 to skip the test instead"
+        ),
+    }
+}
+
+/// Creates a headless renderer of the given size with the [`test_config`], see
+/// [`headless_renderer_with`].
+pub fn headless_renderer(size: PhysicalSize<u32>) -> Option<Renderer> {
+    headless_renderer_with(size, test_config())
+}
+
+/// Returns a camera 2 units in front of the origin looking at it, with a square aspect ratio.
+pub fn fixed_camera() -> Camera {
+    Camera {
+        position: vec3(0.0, 0.0, 2.0),
+        yaw: 0.0,
+        pitch: 0.0,
+        fov: 45.0f32.to_radians(),
+        aspect_ratio: 1.0,
+        movement_sensitivity: 0.0,
+        mouse_sensitivity: 0.0,
+    }
+}
+
+/// Renders a single frame with the given camera, without any UI.
+pub fn render(renderer: &mut Renderer, camera: &Camera) {
+    renderer
+        .render(
+            camera,
+            &egui::Context::default(),
+            egui::FullOutput::default(),
+            || {},
+        )
+        .unwrap();
+}
+
+/// Renders a single frame with the given camera and reads it back.
+pub fn render_capture(renderer: &mut Renderer, camera: &Camera) -> FrameCapture {
+    renderer.request_capture();
+    render(renderer, camera);
+
+    renderer
+        .take_capture()
+        .expect("the rendered frame should have been captured")
+}
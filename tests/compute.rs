@@ -0,0 +1,226 @@
+//! Tests for compute shaders, dispatched by a headless [`Renderer`] and read back from the GPU.
+
+mod common;
+
+use gpu_template::renderer::{
+    capture::PendingCapture,
+    compute::{self, ComputeDispatch, ComputeShader},
+};
+use wgpu::{
+    BindingResource, Buffer, BufferDescriptor, BufferUsages, CommandEncoderDescriptor, Extent3d,
+    TextureDescriptor, TextureDimension, TextureFormat, TextureUsages, TextureViewDescriptor,
+    util::{BufferInitDescriptor, DeviceExt},
+};
+use winit::dpi::PhysicalSize;
+
+use crate::common::{fixed_camera, headless_renderer, render};
+
+/// The size of every headless renderer.
+const SIZE: PhysicalSize<u32> = PhysicalSize::new(32, 32);
+
+/// Doubles every value of a storage buffer.
+const DOUBLE_SHADER: &str = "
+@group(0) @binding(0)
+var<storage, read_write> values: array<u32>;
+
+@compute @workgroup_size(64)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x < arrayLength(&values) {
+        values[id.x] *= 2u;
+    }
+}
+";
+
+/// Fills a storage texture with a gradient of its coordinates.
+const GRADIENT_SHADER: &str = "
+@group(0) @binding(0)
+var output: texture_storage_2d<rgba8unorm, write>;
+
+@compute @workgroup_size(8, 8)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(output);
+
+    if id.x < size.x && id.y < size.y {
+        textureStore(output, id.xy, vec4(f32(id.x) / 255.0, f32(id.y) / 255.0, 1.0, 1.0));
+    }
+}
+";
+
+#[test]
+fn dispatches_run_during_render() {
+    let Some(mut renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    let shader = ComputeShader::new(&renderer.device, "double", DOUBLE_SHADER, "cs_main").unwrap();
+
+    let values = (0..100).collect::<Vec<u32>>();
+
+    let buffer = renderer.device.create_buffer_init(&BufferInitDescriptor {
+        label: Some("values"),
+        contents: bytemuck::cast_slice(&values),
+        usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+    });
+
+    let bind_group = shader.create_bind_group(&renderer.device, 0, &[buffer.as_entire_binding()]);
+
+    let dispatch = ComputeDispatch::new(&shader, vec![bind_group], [values.len() as u32, 1, 1]);
+    assert_eq!(dispatch.workgroups, [2, 1, 1]);
+
+    // dispatches only run once, during the frame after being queued
+    renderer.dispatch_compute(dispatch.clone());
+    renderer.dispatch_compute(dispatch);
+
+    render(&mut renderer, &fixed_camera());
+    render(&mut renderer, &fixed_camera());
+
+    let doubled: Vec<u32> =
+        compute::read_buffer(&renderer.device, &renderer.queue, &buffer).unwrap();
+
+    assert_eq!(doubled, values.iter().map(|x| x * 4).collect::<Vec<_>>());
+}
+
+#[test]
+fn dispatches_write_storage_textures() {
+    let Some(mut renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    let shader =
+        ComputeShader::new(&renderer.device, "gradient", GRADIENT_SHADER, "cs_main").unwrap();
+
+    let texture = renderer.device.create_texture(&TextureDescriptor {
+        label: Some("gradient"),
+        size: Extent3d {
+            width: 20,
+            height: 12,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format: TextureFormat::Rgba8Unorm,
+        usage: TextureUsages::STORAGE_BINDING | TextureUsages::COPY_SRC,
+        view_formats: &[],
+    });
+
+    let view = texture.create_view(&TextureViewDescriptor::default());
+    let bind_group =
+        shader.create_bind_group(&renderer.device, 0, &[BindingResource::TextureView(&view)]);
+
+    renderer.dispatch_compute(ComputeDispatch::new(&shader, vec![bind_group], [20, 12, 1]));
+    render(&mut renderer, &fixed_camera());
+
+    let mut encoder = renderer
+        .device
+        .create_command_encoder(&CommandEncoderDescriptor::default());
+    let pending = PendingCapture::record(&renderer.device, &mut encoder, &texture);
+    renderer.queue.submit([encoder.finish()]);
+
+    let capture = pending.read(&renderer.device).unwrap();
+
+    for (i, pixel) in capture.pixels.chunks_exact(4).enumerate() {
+        let (x, y) = (i as u8 % 20, i as u8 / 20);
+        assert_eq!(pixel, [x, y, 255, 255], "pixel ({x}, {y}) differs");
+    }
+}
+
+#[test]
+fn workgroups_are_rounded_up() {
+    let Some(renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    let shader =
+        ComputeShader::new(&renderer.device, "gradient", GRADIENT_SHADER, "cs_main").unwrap();
+
+    assert_eq!(shader.workgroup_size, [8, 8, 1]);
+    assert_eq!(shader.workgroups_for([1, 8, 1]), [1, 1, 1]);
+    assert_eq!(shader.workgroups_for([9, 17, 3]), [2, 3, 3]);
+    assert_eq!(shader.workgroups_for([0, 0, 0]), [0, 0, 0]);
+}
+
+#[test]
+fn invalid_shaders_are_rejected() {
+    let Some(renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    let missing = ComputeShader::new(&renderer.device, "double", DOUBLE_SHADER, "cs_other");
+    assert!(missing.is_err());
+
+    let invalid = ComputeShader::new(
+        &renderer.device,
+        "invalid",
+        "@compute @workgroup_size(1) fn cs_main() { let x: u32 = 1.0; }",
+        "cs_main",
+    );
+
+    let error = format!("{:#}", invalid.err().unwrap());
+    assert!(error.contains("compute shader invalid"), "{error}");
+}
+
+#[test]
+fn shaders_needing_missing_features_are_rejected() {
+    let Some(renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    // none of these features are requested by the default configuration
+    let shaders = [
+        "var<push_constant> scale: f32;
+        @compute @workgroup_size(1) fn cs_main() { let x = scale; }",
+        "@compute @workgroup_size(1) fn cs_main() { let x: f64 = 1.0; }",
+        "@compute @workgroup_size(1) fn cs_main() { var query: ray_query; }",
+    ];
+
+    for source in shaders {
+        let unsupported = ComputeShader::new(&renderer.device, "unsupported", source, "cs_main");
+
+        let error = format!("{:#}", unsupported.err().unwrap());
+        assert!(error.contains("compute shader unsupported"), "{error}");
+    }
+}
+
+#[test]
+fn empty_buffers_are_read_back() {
+    let Some(renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    let buffer = renderer.device.create_buffer(&BufferDescriptor {
+        label: Some("empty"),
+        size: 0,
+        usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    });
+
+    let values = compute::read_buffer::<u32>(&renderer.device, &renderer.queue, &buffer).unwrap();
+    assert!(values.is_empty());
+}
+
+#[test]
+fn unaligned_buffers_are_rejected() {
+    let Some(renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    let buffer = |size| {
+        renderer.device.create_buffer(&BufferDescriptor {
+            label: Some("unaligned"),
+            size,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        })
+    };
+
+    let read =
+        |buffer: &Buffer| compute::read_buffer::<u16>(&renderer.device, &renderer.queue, buffer);
+
+    // three u16s can't be copied, as copies are made of 4 byte words
+    assert!(read(&buffer(6)).is_err());
+    assert_eq!(read(&buffer(8)).unwrap(), [0; 4]);
+
+    let error = compute::read_buffer::<[u32; 3]>(&renderer.device, &renderer.queue, &buffer(8));
+    assert!(error.is_err());
+}
@@ -0,0 +1,204 @@
+//! Tests for validating a [`RendererConfig`] against the available adapter.
+
+mod common;
+
+use gpu_template::renderer::{
+    Renderer,
+    config::{AdapterSelection, ConfigError, RendererConfig},
+};
+use wgpu::{AdapterInfo, Backend, Backends, DeviceType, Features, Limits};
+use winit::dpi::PhysicalSize;
+
+use crate::common::{headless_renderer, test_config};
+
+/// The size of every headless renderer.
+const SIZE: PhysicalSize<u32> = PhysicalSize::new(16, 16);
+
+/// Creates a headless renderer with the given configuration, returning its error.
+///
+/// Returns `None` if the machine has no usable adapter at all and the test should be skipped.
+fn config_error(config: RendererConfig) -> Option<ConfigError> {
+    headless_renderer(SIZE)?;
+
+    let error = pollster::block_on(Renderer::new_headless(SIZE, config))
+        .err()
+        .expect("the configuration should have been rejected");
+
+    Some(
+        error
+            .downcast::<ConfigError>()
+            .expect("the error should come from validating the configuration"),
+    )
+}
+
+#[test]
+fn missing_features_are_reported() {
+    let Some(error) = config_error(RendererConfig {
+        required_features: Features::all(),
+        ..test_config()
+    }) else {
+        return;
+    };
+
+    let ConfigError::MissingFeatures(missing) = error else {
+        panic!("unexpected error: {error}");
+    };
+
+    assert!(!missing.is_empty());
+    assert!(Features::all().contains(missing));
+}
+
+#[test]
+fn exceeded_limits_are_reported() {
+    let Some(error) = config_error(RendererConfig {
+        required_limits: Limits {
+            max_texture_dimension_2d: u32::MAX,
+            ..Default::default()
+        },
+        ..test_config()
+    }) else {
+        return;
+    };
+
+    let ConfigError::ExceededLimits(limits) = &error else {
+        panic!("unexpected error: {error}");
+    };
+
+    assert_eq!(limits.len(), 1);
+    assert_eq!(limits[0].name, "max_texture_dimension_2d");
+    assert_eq!(limits[0].required, u32::MAX as u64);
+    assert!(error.to_string().contains("max_texture_dimension_2d"));
+}
+
+#[test]
+fn unusual_sample_counts_need_adapter_specific_features() {
+    let features = |sample_count| {
+        RendererConfig {
+            sample_count,
+            ..Default::default()
+        }
+        .features()
+    };
+
+    assert_eq!(features(1), Features::empty());
+    assert_eq!(features(4), Features::empty());
+    assert_eq!(
+        features(8),
+        Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
+    );
+}
+
+#[test]
+fn headless_configs_allow_every_backend() {
+    let config = RendererConfig::headless();
+
+    assert_eq!(config.backends, Backends::all());
+    assert_eq!(RendererConfig::default().backends, Backends::PRIMARY);
+}
+
+#[test]
+fn adapter_selections_are_parsed() {
+    assert_eq!(AdapterSelection::parse("1"), AdapterSelection::Index(1));
+    assert_eq!(AdapterSelection::parse(" 12 "), AdapterSelection::Index(12));
+    assert_eq!(
+        AdapterSelection::parse("llvmpipe"),
+        AdapterSelection::Name("llvmpipe".to_owned())
+    );
+}
+
+#[test]
+fn adapters_are_selected_by_index_or_name() {
+    let info = AdapterInfo {
+        name: "NVIDIA GeForce RTX 3060".to_owned(),
+        vendor: 0,
+        device: 0,
+        device_type: DeviceType::DiscreteGpu,
+        driver: String::new(),
+        driver_info: String::new(),
+        backend: Backend::Vulkan,
+    };
+
+    assert!(AdapterSelection::Index(2).matches(2, &info));
+    assert!(!AdapterSelection::Index(2).matches(0, &info));
+    assert!(AdapterSelection::parse("geforce").matches(0, &info));
+    assert!(!AdapterSelection::parse("radeon").matches(0, &info));
+}
+
+#[test]
+fn unavailable_adapter_selections_are_cleared() {
+    let adapters = [AdapterInfo {
+        name: "llvmpipe (LLVM 19.1.7, 256 bits)".to_owned(),
+        vendor: 0,
+        device: 0,
+        device_type: DeviceType::Cpu,
+        driver: String::new(),
+        driver_info: String::new(),
+        backend: Backend::Vulkan,
+    }];
+
+    let mut config = RendererConfig {
+        adapter: Some(AdapterSelection::parse("llvmpipe")),
+        ..test_config()
+    };
+
+    assert_eq!(config.clear_unavailable_adapter(&adapters), None);
+    assert!(config.adapter.is_some());
+
+    for selection in ["llvmpip3", "1"] {
+        config.adapter = Some(AdapterSelection::parse(selection));
+
+        assert_eq!(
+            config.clear_unavailable_adapter(&adapters),
+            Some(AdapterSelection::parse(selection))
+        );
+        assert_eq!(config.adapter, None);
+    }
+
+    // without a selection, there is nothing to clear
+    assert_eq!(config.clear_unavailable_adapter(&[]), None);
+}
+
+#[test]
+fn selected_adapters_are_used() {
+    let Some(renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    let available = test_config().available_adapters();
+    let index = available
+        .iter()
+        .position(|info| info.name == renderer.adapter_info().name)
+        .expect("the default adapter should be listed");
+
+    for selection in [
+        AdapterSelection::Index(index),
+        AdapterSelection::Name(available[index].name.to_uppercase()),
+    ] {
+        let config = RendererConfig {
+            adapter: Some(selection),
+            ..test_config()
+        };
+
+        let selected = pollster::block_on(Renderer::new_headless(SIZE, config)).unwrap();
+
+        assert_eq!(selected.adapter_info().name, available[index].name);
+    }
+}
+
+#[test]
+fn missing_adapters_are_reported() {
+    if headless_renderer(SIZE).is_none() {
+        return;
+    }
+
+    let config = RendererConfig {
+        adapter: Some(AdapterSelection::Name("no such adapter".to_owned())),
+        ..test_config()
+    };
+
+    let error = pollster::block_on(Renderer::new_headless(SIZE, config))
+        .err()
+        .expect("a missing adapter should be rejected");
+
+    assert!(error.to_string().contains("available: #0"), "{error}");
+}
@@ -0,0 +1,557 @@
+//! Golden-image regression tests, rendering scenes with a headless [`Renderer`] and comparing them
+//! against the reference images checked into `tests/golden/`.
+//!
+//! Run with `GOLDEN_BLESS=1` to (re)generate the reference images from the current output. The
+//! tests fail on machines without a usable adapter, unless run with `GOLDEN_SKIP_WITHOUT_ADAPTER=1`.
+
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use glam::{Mat4, Quat, Vec3, vec2, vec3, vec4};
+use gpu_template::renderer::{
+    Renderer,
+    camera::Camera,
+    capture::FrameCapture,
+    config::RendererConfig,
+    instance::MeshInstance,
+    material::{MaterialData, MaterialId},
+    mesh::{MeshData, Vertex},
+    particles::EmitterSettings,
+    texture::ColorSpace,
+    tonemap::TonemapOperator,
+};
+use image::{Rgba, RgbaImage};
+use wgpu::TextureFormat;
+use winit::dpi::PhysicalSize;
+
+use crate::common::{
+    fixed_camera, headless_renderer, headless_renderer_with, render_capture, test_config,
+};
+
+/// The size of every rendered golden image.
+const SIZE: PhysicalSize<u32> = PhysicalSize::new(128, 128);
+
+/// The maximum difference allowed between any channel of a rendered and a reference pixel.
+const TOLERANCE: u8 = 4;
+
+/// Returns a camera looking at the origin from the side, so that the edges of the triangle are
+/// slanted.
+fn side_camera() -> Camera {
+    Camera {
+        position: vec3(-1.5, 0.5, 1.5),
+        yaw: 45.0f32.to_radians(),
+        pitch: -10.0f32.to_radians(),
+        ..fixed_camera()
+    }
+}
+
+/// Returns a single colored square at the given depth.
+fn square(z: f32, half_size: f32, color: Vec3) -> MeshData {
+    let corners = [
+        vec2(-1.0, -1.0),
+        vec2(1.0, -1.0),
+        vec2(1.0, 1.0),
+        vec2(-1.0, 1.0),
+    ];
+
+    let vertices = corners
+        .into_iter()
+        .map(|corner| Vertex {
+            position: (corner * half_size).extend(z),
+            normal: Vec3::Z,
+            tex_coords: corner * 0.5 + 0.5,
+            color,
+        })
+        .collect();
+
+    MeshData {
+        vertices,
+        indices: vec![0, 1, 2, 0, 2, 3],
+    }
+}
+
+/// Uploads a square with a tinted checkerboard texture.
+fn upload_textured_square(renderer: &mut Renderer) {
+    let checker = RgbaImage::from_fn(4, 4, |x, y| match (x + y) % 2 {
+        0 => Rgba([255, 255, 255, 255]),
+        _ => Rgba([40, 40, 40, 255]),
+    });
+
+    let texture = renderer.upload_texture(&checker, ColorSpace::Srgb);
+    let material = renderer.upload_material(
+        &MaterialData {
+            base_color_factor: vec4(1.0, 0.8, 0.6, 1.0),
+            base_color_texture: Some(0),
+            ..Default::default()
+        },
+        &[texture],
+    );
+
+    renderer.upload_mesh(&square(0.0, 0.6, Vec3::ONE), material);
+}
+
+/// Returns the path of the reference image with the given name.
+fn reference_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.png"))
+}
+
+/// Returns the directory any failing output and diff images are written into.
+fn failure_directory() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("target/golden-failures")
+}
+
+/// Compares the capture against the reference image with the given name, writing the output and
+/// a diff image into the [`failure_directory`] on failure.
+fn assert_matches_golden(name: &str, capture: &FrameCapture) {
+    let reference_path = reference_path(name);
+
+    if std::env::var_os("GOLDEN_BLESS").is_some() {
+        capture.save_png(&reference_path).unwrap();
+        return;
+    }
+
+    let reference = image::open(&reference_path)
+        .unwrap_or_else(|e| {
+            panic!(
+                "failed to open {} ({e}), run with GOLDEN_BLESS=1 to create it",
+                reference_path.display()
+            )
+        })
+        .into_rgba8();
+
+    assert_eq!(
+        reference.dimensions(),
+        (capture.width, capture.height),
+        "{name}: size does not match the reference image"
+    );
+
+    let mut mismatched = 0;
+    let mut diff = Vec::with_capacity(capture.pixels.len());
+
+    for (actual, expected) in capture.pixels.chunks_exact(4).zip(reference.pixels()) {
+        let error = actual
+            .iter()
+            .zip(expected.0)
+            .map(|(a, e)| a.abs_diff(e))
+            .max()
+            .unwrap_or(0);
+
+        if error > TOLERANCE {
+            mismatched += 1;
+            diff.extend_from_slice(&[255, 0, 255, 255]);
+        } else {
+            // faded out copy of the reference, so mismatches stand out
+            diff.extend(expected.0[..3].iter().map(|c| c / 4));
+            diff.push(255);
+        }
+    }
+
+    if mismatched == 0 {
+        return;
+    }
+
+    let directory = failure_directory();
+    std::fs::create_dir_all(&directory).unwrap();
+
+    let actual_path = directory.join(format!("{name}-actual.png"));
+    let diff_path = directory.join(format!("{name}-diff.png"));
+
+    capture.save_png(&actual_path).unwrap();
+
+    FrameCapture {
+        pixels: diff,
+        ..capture.clone()
+    }
+    .save_png(&diff_path)
+    .unwrap();
+
+    panic!(
+        "{name}: {mismatched} pixels differ from the reference by more than {TOLERANCE}, see {} \
+         and {}",
+        actual_path.display(),
+        diff_path.display()
+    );
+}
+
+#[test]
+fn triangle() {
+    let Some(mut renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    renderer.upload_mesh(&MeshData::triangle(), MaterialId::DEFAULT);
+
+    let capture = render_capture(&mut renderer, &fixed_camera());
+
+    assert_matches_golden("triangle", &capture);
+}
+
+#[test]
+fn triangle_from_the_side() {
+    let Some(mut renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    renderer.upload_mesh(&MeshData::triangle(), MaterialId::DEFAULT);
+
+    let capture = render_capture(&mut renderer, &side_camera());
+
+    assert_matches_golden("triangle_from_the_side", &capture);
+}
+
+#[test]
+fn overlapping_meshes_are_depth_sorted() {
+    let Some(mut renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    // the nearer square is drawn first, so it is only visible if depth testing works
+    renderer.upload_mesh(&square(0.0, 0.4, vec3(1.0, 0.5, 0.0)), MaterialId::DEFAULT);
+    renderer.upload_mesh(&square(-0.5, 0.6, vec3(0.0, 0.5, 1.0)), MaterialId::DEFAULT);
+
+    let capture = render_capture(&mut renderer, &fixed_camera());
+
+    assert_matches_golden("overlapping_meshes_are_depth_sorted", &capture);
+}
+
+#[test]
+fn depth_format_can_change() {
+    let Some(mut renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    renderer.upload_mesh(&square(0.0, 0.4, vec3(1.0, 0.5, 0.0)), MaterialId::DEFAULT);
+    renderer.upload_mesh(&square(-0.5, 0.6, vec3(0.0, 0.5, 1.0)), MaterialId::DEFAULT);
+
+    renderer
+        .set_depth_format(TextureFormat::Depth24Plus)
+        .unwrap();
+
+    // formats without depth are rejected, keeping the previous format
+    assert!(
+        renderer
+            .set_depth_format(TextureFormat::Rgba8Unorm)
+            .is_err()
+    );
+
+    let capture = render_capture(&mut renderer, &fixed_camera());
+
+    assert_matches_golden("overlapping_meshes_are_depth_sorted", &capture);
+}
+
+#[test]
+fn instanced_meshes() {
+    let Some(mut renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    let mesh = renderer.upload_mesh(&square(0.0, 1.0, Vec3::ONE), MaterialId::DEFAULT);
+
+    // the first frame draws a single instance, so the instance buffer has to grow afterwards
+    render_capture(&mut renderer, &fixed_camera());
+
+    let count = 16;
+    let spacing = 1.6 / count as f32;
+
+    renderer.mesh_mut(mesh).instances = (0..count * count)
+        .map(|i| {
+            let (x, y) = ((i % count) as f32, (i / count) as f32);
+
+            MeshInstance {
+                transform: Mat4::from_scale_rotation_translation(
+                    Vec3::splat(spacing * 0.4),
+                    Quat::from_rotation_z(i as f32 * 0.1),
+                    vec3(x + 0.5, y + 0.5, 0.0) * spacing - vec3(0.8, 0.8, 0.0),
+                ),
+                color: vec4(x / count as f32, y / count as f32, 0.5, 1.0),
+            }
+        })
+        .collect();
+
+    let capture = render_capture(&mut renderer, &fixed_camera());
+
+    assert_eq!(renderer.mesh_mut(mesh).instance_count(), 256);
+    assert_matches_golden("instanced_meshes", &capture);
+}
+
+#[test]
+fn textured_material() {
+    let Some(mut renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    upload_textured_square(&mut renderer);
+
+    let capture = render_capture(&mut renderer, &fixed_camera());
+
+    assert_matches_golden("textured_material", &capture);
+}
+
+#[test]
+fn rendering_survives_device_loss() {
+    let Some(mut renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    upload_textured_square(&mut renderer);
+
+    render_capture(&mut renderer, &fixed_camera());
+
+    // destroying the device loses it, just like a driver reset would
+    renderer.device.destroy();
+    let _ = renderer.device.poll(wgpu::PollType::Poll);
+
+    let capture = render_capture(&mut renderer, &fixed_camera());
+
+    assert_matches_golden("textured_material", &capture);
+}
+
+#[test]
+fn missing_material_textures_are_white() {
+    let Some(mut renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    let untextured = renderer.upload_material(&MaterialData::default(), &[]);
+    let mesh = renderer.upload_mesh(&square(0.0, 0.6, Vec3::ONE), untextured);
+
+    let expected = render_capture(&mut renderer, &fixed_camera());
+
+    renderer.mesh_mut(mesh).instances.clear();
+
+    let missing = renderer.upload_material(
+        &MaterialData {
+            base_color_texture: Some(3),
+            emissive_texture: Some(0),
+            ..Default::default()
+        },
+        &[],
+    );
+    renderer.upload_mesh(&square(0.0, 0.6, Vec3::ONE), missing);
+
+    assert_eq!(
+        render_capture(&mut renderer, &fixed_camera()).pixels,
+        expected.pixels
+    );
+
+    // materials are uploaded again after the device is lost
+    renderer.device.destroy();
+    let _ = renderer.device.poll(wgpu::PollType::Poll);
+
+    assert_eq!(
+        render_capture(&mut renderer, &fixed_camera()).pixels,
+        expected.pixels
+    );
+}
+
+#[test]
+fn multisampled_triangle() {
+    let config = RendererConfig {
+        sample_count: 4,
+        ..test_config()
+    };
+
+    let Some(mut renderer) = headless_renderer_with(SIZE, config) else {
+        return;
+    };
+
+    renderer.upload_mesh(&MeshData::triangle(), MaterialId::DEFAULT);
+
+    let capture = render_capture(&mut renderer, &side_camera());
+
+    assert_matches_golden("multisampled_triangle", &capture);
+}
+
+#[test]
+fn sample_count_can_change_between_frames() {
+    let Some(mut renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    if !renderer.sample_counts().contains(&4) {
+        eprintln!("skipping golden test, 4x MSAA is not supported");
+        return;
+    }
+
+    renderer.upload_mesh(&MeshData::triangle(), MaterialId::DEFAULT);
+
+    let aliased = render_capture(&mut renderer, &side_camera());
+    renderer.set_sample_count(4);
+
+    let capture = render_capture(&mut renderer, &side_camera());
+
+    assert_eq!(renderer.sample_count(), 4);
+    assert_ne!(
+        aliased.pixels, capture.pixels,
+        "the edges should have been smoothed"
+    );
+    assert_matches_golden("multisampled_triangle", &capture);
+}
+
+#[test]
+fn tonemap_operators() {
+    let Some(mut renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    // colors far beyond 1.0 are where the operators differ the most
+    renderer.upload_mesh(&square(0.0, 0.4, vec3(6.0, 2.0, 0.5)), MaterialId::DEFAULT);
+    renderer.upload_mesh(&square(-0.5, 0.6, vec3(0.2, 0.6, 1.5)), MaterialId::DEFAULT);
+
+    for (operator, name) in [
+        (TonemapOperator::Aces, "tonemap_aces"),
+        (TonemapOperator::Reinhard, "tonemap_reinhard"),
+        (TonemapOperator::AgX, "tonemap_agx"),
+    ] {
+        renderer.tonemap_settings.operator = operator;
+
+        let capture = render_capture(&mut renderer, &fixed_camera());
+
+        assert_matches_golden(name, &capture);
+    }
+}
+
+#[test]
+fn exposure_scales_brightness() {
+    let Some(mut renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    renderer.upload_mesh(&MeshData::triangle(), MaterialId::DEFAULT);
+
+    let brightness =
+        |capture: &FrameCapture| -> u64 { capture.pixels.iter().map(|&c| u64::from(c)).sum() };
+
+    let default = brightness(&render_capture(&mut renderer, &fixed_camera()));
+
+    renderer.tonemap_settings.exposure = -2.0;
+    let darker = brightness(&render_capture(&mut renderer, &fixed_camera()));
+
+    renderer.tonemap_settings.exposure = 2.0;
+    let brighter = brightness(&render_capture(&mut renderer, &fixed_camera()));
+
+    assert!(
+        darker < default,
+        "lowering the exposure should darken the frame"
+    );
+    assert!(
+        brighter > default,
+        "raising the exposure should brighten the frame"
+    );
+}
+
+#[test]
+fn bloom() {
+    let Some(mut renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    // only the bright square is above the threshold, so only it should glow
+    renderer.upload_mesh(&square(0.0, 0.2, vec3(8.0, 4.0, 1.0)), MaterialId::DEFAULT);
+    renderer.upload_mesh(&square(-0.5, 0.6, vec3(0.1, 0.2, 0.4)), MaterialId::DEFAULT);
+
+    renderer.post_settings.bloom.enabled = true;
+
+    let capture = render_capture(&mut renderer, &fixed_camera());
+
+    assert_matches_golden("bloom", &capture);
+}
+
+#[test]
+fn particles_face_the_camera() {
+    let Some(mut renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    renderer.upload_mesh(&square(0.0, 0.3, vec3(0.1, 0.2, 0.4)), MaterialId::DEFAULT);
+
+    // a single particle which never moves, in front of the square and hidden behind it
+    let particle = |position, color| EmitterSettings {
+        position,
+        speed: 0.0,
+        spawn_rate: 1.0,
+        gravity: Vec3::ZERO,
+        start_color: color,
+        size: 0.4,
+        ..Default::default()
+    };
+
+    renderer.add_emitter(particle(vec3(-0.3, 0.2, 0.3), vec4(1.0, 0.5, 0.2, 1.0)), 1);
+    renderer.add_emitter(particle(vec3(0.1, 0.0, -0.3), vec4(0.2, 1.0, 0.2, 1.0)), 1);
+
+    renderer.update_particles(1.0);
+
+    let capture = render_capture(&mut renderer, &side_camera());
+
+    assert_matches_golden("particles_face_the_camera", &capture);
+}
+
+#[test]
+fn post_effects() {
+    let Some(mut renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    upload_textured_square(&mut renderer);
+
+    // swaps the red and blue channels of every color
+    let size = 8;
+    let scale = |channel: u32| (channel * 255 / (size - 1)) as u8;
+    let swap = RgbaImage::from_fn(size * size, size, |x, y| {
+        Rgba([scale(x / size), scale(y), scale(x % size), 255])
+    });
+
+    renderer.set_color_lut(&swap).unwrap();
+
+    let settings = &mut renderer.post_settings;
+
+    settings.fxaa.enabled = true;
+    settings.chromatic_aberration.enabled = true;
+    settings.color_grading.enabled = true;
+    settings.vignette.enabled = true;
+
+    let capture = render_capture(&mut renderer, &side_camera());
+
+    assert_matches_golden("post_effects", &capture);
+
+    // the textures (and bind groups) read by effects are reused by later frames, even after
+    // some effects were disabled for a while
+    renderer.post_settings.chromatic_aberration.enabled = false;
+    render_capture(&mut renderer, &side_camera());
+    renderer.post_settings.chromatic_aberration.enabled = true;
+
+    for _ in 0..2 {
+        let capture = render_capture(&mut renderer, &side_camera());
+
+        assert_matches_golden("post_effects", &capture);
+    }
+}
+
+#[test]
+fn identity_color_grading_changes_nothing() {
+    let Some(mut renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    upload_textured_square(&mut renderer);
+
+    renderer.post_settings.color_grading.enabled = true;
+
+    let capture = render_capture(&mut renderer, &fixed_camera());
+
+    assert_matches_golden("textured_material", &capture);
+}
+
+#[test]
+fn color_luts_must_be_strips() {
+    let Some(mut renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    let square = RgbaImage::new(16, 16);
+
+    assert!(renderer.set_color_lut(&square).is_err());
+}
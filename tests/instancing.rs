@@ -0,0 +1,97 @@
+//! Tests for drawing many instances of a mesh, with a headless [`Renderer`].
+
+mod common;
+
+use glam::{Mat4, vec3};
+use gpu_template::renderer::{
+    instance::{InstanceBuffer, MeshInstance},
+    material::MaterialId,
+    mesh::MeshData,
+};
+use winit::dpi::PhysicalSize;
+
+use crate::common::{fixed_camera, headless_renderer, render, render_capture};
+
+/// The size of every headless renderer.
+const SIZE: PhysicalSize<u32> = PhysicalSize::new(32, 32);
+
+#[test]
+fn instance_buffers_grow_to_fit() {
+    let Some(renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    let (device, queue) = (&renderer.device, &renderer.queue);
+    let mut buffer = InstanceBuffer::new(device, "instances");
+
+    assert!(buffer.is_empty());
+    assert_eq!(buffer.capacity(), 1);
+
+    buffer.write(device, queue, &[MeshInstance::default(); 3]);
+    assert_eq!((buffer.len(), buffer.capacity()), (3, 4));
+
+    buffer.write(device, queue, &vec![MeshInstance::default(); 5000]);
+    assert_eq!((buffer.len(), buffer.capacity()), (5000, 8192));
+
+    // the buffer never shrinks, so it can be reused by the next frame
+    buffer.write(device, queue, &[]);
+    assert!(buffer.is_empty());
+    assert_eq!(buffer.capacity(), 8192);
+    assert_eq!(
+        buffer.buffer().size(),
+        8192 * size_of::<MeshInstance>() as u64
+    );
+}
+
+#[test]
+fn meshes_are_drawn_once_by_default() {
+    let Some(mut renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    let mesh = renderer.upload_mesh(&MeshData::triangle(), MaterialId::DEFAULT);
+
+    assert_eq!(renderer.mesh_mut(mesh).instances, [MeshInstance::default()]);
+
+    render(&mut renderer, &fixed_camera());
+
+    assert_eq!(renderer.mesh_mut(mesh).instance_count(), 1);
+}
+
+#[test]
+fn instances_are_updated_every_frame() {
+    let Some(mut renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    let camera = fixed_camera();
+    let empty = render_capture(&mut renderer, &camera);
+    let mesh = renderer.upload_mesh(&MeshData::triangle(), MaterialId::DEFAULT);
+
+    // meshes without instances draw nothing
+    renderer.mesh_mut(mesh).instances.clear();
+    assert_eq!(render_capture(&mut renderer, &camera).pixels, empty.pixels);
+
+    // moved out of view, then back
+    renderer.mesh_mut(mesh).instances = vec![MeshInstance::new(Mat4::from_translation(vec3(
+        10.0, 0.0, 0.0,
+    )))];
+    assert_eq!(render_capture(&mut renderer, &camera).pixels, empty.pixels);
+
+    renderer.mesh_mut(mesh).instances[0] = MeshInstance::default();
+    assert_ne!(render_capture(&mut renderer, &camera).pixels, empty.pixels);
+}
+
+#[test]
+fn empty_meshes_draw_nothing() {
+    let Some(mut renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    let camera = fixed_camera();
+    let empty = render_capture(&mut renderer, &camera);
+    let mesh = renderer.upload_mesh(&MeshData::default(), MaterialId::DEFAULT);
+
+    assert_eq!(renderer.mesh_mut(mesh).index_count, 0);
+    assert_eq!(render_capture(&mut renderer, &camera).pixels, empty.pixels);
+}
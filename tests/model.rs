@@ -0,0 +1,193 @@
+//! Tests for loading models from the files in `tests/assets/`.
+
+use std::path::{Path, PathBuf};
+
+use glam::{Vec3, vec2, vec3, vec4};
+use gpu_template::renderer::{
+    model::{
+        Model,
+        obj::{self, NormalGeneration},
+    },
+    texture::ColorSpace,
+};
+
+/// Returns the path of the test asset with the given name.
+fn asset_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/assets")
+        .join(name)
+}
+
+#[test]
+fn gltf_flattens_node_hierarchy() {
+    let model = Model::load(asset_path("hierarchy.gltf")).unwrap();
+
+    assert_eq!(model.meshes.len(), 1);
+
+    let mesh = &model.meshes[0];
+    let positions = mesh
+        .data
+        .vertices
+        .iter()
+        .map(|vertex| vertex.position)
+        .collect::<Vec<_>>();
+
+    // the child's scale is applied before the parent's translation
+    assert_eq!(mesh.name.as_deref(), Some("triangle"));
+    assert_eq!(
+        positions,
+        [
+            vec3(1.0, 0.0, 0.0),
+            vec3(3.0, 0.0, 0.0),
+            vec3(1.0, 2.0, 0.0)
+        ]
+    );
+}
+
+#[test]
+fn gltf_generates_missing_normals() {
+    let model = Model::load(asset_path("hierarchy.gltf")).unwrap();
+
+    for vertex in &model.meshes[0].data.vertices {
+        assert_eq!(vertex.normal, Vec3::Z);
+    }
+}
+
+#[test]
+fn gltf_keeps_zero_length_normals() {
+    let model = Model::load(asset_path("zero_normals.gltf")).unwrap();
+    let normals = model.meshes[0]
+        .data
+        .vertices
+        .iter()
+        .map(|vertex| vertex.normal)
+        .collect::<Vec<_>>();
+
+    // degenerate normals can't be normalized, and shouldn't turn into NaNs
+    assert_eq!(normals, [Vec3::ZERO, Vec3::Z, Vec3::Z]);
+}
+
+#[test]
+fn gltf_reads_vertex_attributes() {
+    let model = Model::load(asset_path("hierarchy.gltf")).unwrap();
+    let vertices = &model.meshes[0].data.vertices;
+
+    assert_eq!(vertices[1].tex_coords, vec2(1.0, 0.0));
+    assert_eq!(vertices[2].color, Vec3::ONE);
+    assert_eq!(model.meshes[0].data.indices, [0, 1, 2]);
+}
+
+#[test]
+fn gltf_reads_materials_and_textures() {
+    let model = Model::load(asset_path("hierarchy.gltf")).unwrap();
+
+    assert_eq!(model.meshes[0].material, Some(0));
+
+    let material = &model.materials[0];
+
+    assert_eq!(material.name.as_deref(), Some("painted"));
+    assert_eq!(material.base_color_factor, vec4(1.0, 0.5, 0.25, 1.0));
+    assert_eq!(material.metallic_factor, 0.25);
+    assert_eq!(material.roughness_factor, 0.75);
+    assert_eq!(material.emissive_factor, vec3(0.1, 0.2, 0.3));
+    assert_eq!(material.base_color_texture, Some(0));
+    assert_eq!(material.emissive_texture, Some(1));
+    assert_eq!(material.normal_texture, None);
+
+    // an external file and an embedded data uri
+    let checker = &model.textures[0];
+    let emissive = &model.textures[1];
+
+    assert_eq!(checker.dimensions(), (2, 2));
+    assert_eq!(checker.get_pixel(1, 0).0, [0, 0, 0, 255]);
+    assert_eq!(emissive.get_pixel(0, 0).0, [10, 20, 30, 255]);
+}
+
+#[test]
+fn gltf_texture_color_spaces_follow_usage() {
+    let model = Model::load(asset_path("hierarchy.gltf")).unwrap();
+
+    assert_eq!(model.texture_color_space(0), ColorSpace::Srgb);
+    assert_eq!(model.texture_color_space(1), ColorSpace::Srgb);
+
+    let mut model = model;
+    model.materials[0].emissive_texture = None;
+    model.materials[0].normal_texture = Some(1);
+
+    assert_eq!(model.texture_color_space(1), ColorSpace::Linear);
+}
+
+#[test]
+fn obj_triangulates_faces() {
+    let model = Model::load(asset_path("cube.obj")).unwrap();
+
+    assert_eq!(model.meshes.len(), 1);
+    assert_eq!(model.meshes[0].name.as_deref(), Some("cube"));
+    assert_eq!(model.meshes[0].data.indices.len(), 6 * 2 * 3);
+}
+
+#[test]
+fn obj_flips_texture_coordinates() {
+    let model = Model::load(asset_path("cube.obj")).unwrap();
+    let vertices = &model.meshes[0].data.vertices;
+
+    // the first vertex of the first face uses `vt 0.0 0.0`
+    assert_eq!(vertices[0].tex_coords, vec2(0.0, 1.0));
+}
+
+#[test]
+fn obj_generates_flat_normals() {
+    let model = obj::load_with(&asset_path("cube.obj"), NormalGeneration::Flat).unwrap();
+    let data = &model.meshes[0].data;
+
+    for triangle in data.indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| data.vertices[triangle[i] as usize]);
+
+        assert_eq!(a.normal, b.normal);
+        assert_eq!(a.normal, c.normal);
+
+        // every face of the cube is axis aligned and faces outwards
+        assert_eq!(a.normal.abs().max_element(), 1.0);
+        assert!(a.normal.dot(a.position) > 0.0);
+    }
+}
+
+#[test]
+fn obj_generates_smooth_normals() {
+    let model = obj::load_with(&asset_path("cube.obj"), NormalGeneration::Smooth).unwrap();
+
+    for vertex in &model.meshes[0].data.vertices {
+        assert!(vertex.normal.is_normalized());
+
+        // every corner is shared by three faces, pointing the normal out diagonally
+        assert!(vertex.normal.x * vertex.position.x > 0.0);
+        assert!(vertex.normal.y * vertex.position.y > 0.0);
+        assert!(vertex.normal.z * vertex.position.z > 0.0);
+    }
+}
+
+#[test]
+fn obj_reads_materials_and_textures() {
+    let model = Model::load(asset_path("cube.obj")).unwrap();
+
+    assert_eq!(model.meshes[0].material, Some(0));
+
+    let material = &model.materials[0];
+
+    assert_eq!(material.name.as_deref(), Some("painted"));
+    assert_eq!(material.base_color_factor, vec4(1.0, 0.5, 0.25, 0.5));
+    assert_eq!(material.metallic_factor, 0.75);
+    assert!((material.roughness_factor - 0.02f32.sqrt()).abs() < 1e-6);
+    assert_eq!(material.emissive_factor, vec3(0.1, 0.2, 0.3));
+
+    // the missing emissive texture is skipped
+    assert_eq!(material.base_color_texture, Some(0));
+    assert_eq!(material.emissive_texture, None);
+    assert_eq!(model.textures.len(), 1);
+    assert_eq!(model.textures[0].dimensions(), (2, 2));
+}
+
+#[test]
+fn unknown_formats_are_rejected() {
+    assert!(Model::load(asset_path("hierarchy.bin")).is_err());
+}
@@ -0,0 +1,194 @@
+//! Tests for the GPU particle simulation, run by a headless [`Renderer`] and read back from the
+//! GPU.
+
+mod common;
+
+use glam::{Vec3, vec3};
+use gpu_template::renderer::{
+    Renderer,
+    particles::{EmitterId, EmitterSettings, Particle},
+};
+use winit::dpi::PhysicalSize;
+
+use crate::common::{fixed_camera, headless_renderer, render};
+
+/// The size of every headless renderer.
+const SIZE: PhysicalSize<u32> = PhysicalSize::new(32, 32);
+
+/// The largest error allowed between floating point results.
+const EPSILON: f32 = 1e-4;
+
+/// The time simulated by every frame (in seconds).
+const DT: f32 = 0.125;
+
+/// Returns emitter settings spawning 10 particles every frame.
+fn settings() -> EmitterSettings {
+    EmitterSettings {
+        position: vec3(1.0, 2.0, 3.0),
+        direction: Vec3::X,
+        cone_angle: 30.0f32.to_radians(),
+        speed: 2.0,
+        spawn_rate: 10.0 / DT,
+        lifetime: 1.0,
+        gravity: vec3(0.0, -10.0, 0.0),
+        ..Default::default()
+    }
+}
+
+/// Simulates and renders a single frame, then reads back every particle of the emitter.
+fn step(renderer: &mut Renderer, emitter: EmitterId) -> Vec<Particle> {
+    renderer.update_particles(DT);
+    render(renderer, &fixed_camera());
+
+    renderer.read_particles(emitter).unwrap()
+}
+
+/// Returns whether the particle is still alive.
+fn alive(particle: &Particle) -> bool {
+    particle.age < particle.lifetime
+}
+
+#[test]
+fn particles_spawn_within_the_cone() {
+    let Some(mut renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    let settings = settings();
+    let emitter = renderer.add_emitter(settings, 64);
+
+    let particles = step(&mut renderer, emitter);
+    assert_eq!(particles.len(), 64);
+
+    // particles are spawned at the start of the ring, with the rest never having been alive
+    for (i, particle) in particles.iter().enumerate() {
+        if i >= 10 {
+            assert_eq!(*particle, Particle::default());
+            continue;
+        }
+
+        assert!(alive(particle));
+        assert_eq!(particle.age, 0.0);
+        assert_eq!(particle.lifetime, settings.lifetime);
+        assert!(particle.position.abs_diff_eq(settings.position, EPSILON));
+
+        let direction = particle.velocity / settings.speed;
+
+        assert!((particle.velocity.length() - settings.speed).abs() < EPSILON);
+        assert!(direction.angle_between(settings.direction) <= settings.cone_angle + EPSILON);
+    }
+
+    // every particle gets its own random direction
+    assert_ne!(particles[0].velocity, particles[1].velocity);
+}
+
+#[test]
+fn particles_fall_under_gravity() {
+    let Some(mut renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    let settings = settings();
+    let emitter = renderer.add_emitter(settings, 64);
+
+    let spawned = step(&mut renderer, emitter);
+
+    renderer.emitter_mut(emitter).settings.spawn_rate = 0.0;
+
+    let moved = step(&mut renderer, emitter);
+
+    for (before, after) in spawned.iter().zip(&moved).take(10) {
+        let velocity = before.velocity + settings.gravity * DT;
+
+        assert!((after.age - DT).abs() < EPSILON);
+        assert!(after.velocity.abs_diff_eq(velocity, EPSILON));
+        assert!(
+            after
+                .position
+                .abs_diff_eq(before.position + velocity * DT, EPSILON)
+        );
+    }
+
+    assert!(moved.iter().skip(10).all(|particle| !alive(particle)));
+}
+
+#[test]
+fn particles_die_after_their_lifetime() {
+    let Some(mut renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    let emitter = renderer.add_emitter(
+        EmitterSettings {
+            lifetime: 1.5 * DT,
+            ..settings()
+        },
+        64,
+    );
+
+    step(&mut renderer, emitter);
+
+    renderer.emitter_mut(emitter).settings.spawn_rate = 0.0;
+
+    assert_eq!(
+        step(&mut renderer, emitter)
+            .iter()
+            .filter(|p| alive(p))
+            .count(),
+        10
+    );
+
+    // dead particles are left where they died
+    let dead = step(&mut renderer, emitter);
+    let after = step(&mut renderer, emitter);
+
+    assert!(dead.iter().all(|particle| !alive(particle)));
+    assert_eq!(dead, after);
+}
+
+#[test]
+fn full_emitters_replace_their_oldest_particles() {
+    let Some(mut renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    let emitter = renderer.add_emitter(settings(), 16);
+
+    step(&mut renderer, emitter);
+    let particles = step(&mut renderer, emitter);
+
+    // the second frame fills the last 6 slots, then wraps around into the first 4
+    let ages = particles
+        .iter()
+        .map(|particle| particle.age)
+        .collect::<Vec<_>>();
+
+    assert!(particles.iter().all(alive));
+    assert!(ages[..4].iter().all(|&age| age == 0.0));
+    assert!(ages[4..10].iter().all(|&age| (age - DT).abs() < EPSILON));
+    assert!(ages[10..].iter().all(|&age| age == 0.0));
+}
+
+#[test]
+fn spawn_rates_carry_over_between_frames() {
+    let Some(mut renderer) = headless_renderer(SIZE) else {
+        return;
+    };
+
+    // 2.5 particles per frame
+    let emitter = renderer.add_emitter(
+        EmitterSettings {
+            spawn_rate: 2.5 / DT,
+            ..settings()
+        },
+        64,
+    );
+
+    let mut particles = Vec::new();
+
+    for _ in 0..4 {
+        particles = step(&mut renderer, emitter);
+    }
+
+    assert_eq!(particles.iter().filter(|p| alive(p)).count(), 10);
+}
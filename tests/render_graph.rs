@@ -0,0 +1,198 @@
+//! Tests for compiling [`RenderGraph`]s, which (besides executing them) don't need a device.
+
+mod common;
+
+use gpu_template::renderer::graph::{GraphError, RenderGraph, TransientTexture};
+use wgpu::{TextureFormat, TextureUsages};
+use winit::dpi::PhysicalSize;
+
+use crate::common::{fixed_camera, headless_renderer, render};
+
+/// The description of every transient texture used by the tests.
+const TEXTURE: TransientTexture = TransientTexture {
+    width: 64,
+    height: 64,
+    format: TextureFormat::Rgba8Unorm,
+    usage: TextureUsages::RENDER_ATTACHMENT.union(TextureUsages::TEXTURE_BINDING),
+};
+
+#[test]
+fn passes_run_after_their_dependencies() {
+    let mut graph = RenderGraph::new();
+
+    let a = graph.create_texture("a", TEXTURE);
+    let b = graph.create_texture("b", TEXTURE);
+
+    graph.add_pass("write_a", |pass| {
+        pass.write(a);
+        |_| {}
+    });
+    graph.add_pass("write_b", |pass| {
+        pass.write(b);
+        |_| {}
+    });
+    graph.add_pass("read_a_write_b", |pass| {
+        pass.read(a).write(b);
+        |_| {}
+    });
+    graph.add_pass("present", |pass| {
+        pass.read(b).side_effects();
+        |_| {}
+    });
+
+    let schedule = graph.compile().unwrap();
+
+    // writes may load what was written before them, so the first write of `b` is kept
+    assert_eq!(
+        schedule.passes,
+        ["write_a", "write_b", "read_a_write_b", "present"]
+    );
+    assert!(schedule.culled.is_empty());
+}
+
+#[test]
+fn passes_without_effects_are_culled() {
+    let mut graph = RenderGraph::new();
+
+    let unused = graph.create_texture("unused", TEXTURE);
+
+    graph.add_pass("unused", |pass| {
+        pass.write(unused);
+        |_| {}
+    });
+    graph.add_pass("readback", |pass| {
+        pass.side_effects();
+        |_| {}
+    });
+
+    let schedule = graph.compile().unwrap();
+
+    assert_eq!(schedule.passes, ["readback"]);
+    assert_eq!(schedule.culled, ["unused"]);
+}
+
+#[test]
+fn reading_unwritten_transients_fails() {
+    let mut graph = RenderGraph::new();
+
+    let texture = graph.create_texture("texture", TEXTURE);
+
+    graph.add_pass("reader", |pass| {
+        pass.read(texture).side_effects();
+        |_| {}
+    });
+
+    assert_eq!(
+        graph.compile(),
+        Err(GraphError::UninitializedRead {
+            pass: "reader".to_owned(),
+            resource: "texture".to_owned(),
+        })
+    );
+}
+
+#[test]
+fn transients_with_disjoint_lifetimes_are_aliased() {
+    let mut graph = RenderGraph::new();
+
+    let chain =
+        ["first", "second", "third", "fourth"].map(|name| graph.create_texture(name, TEXTURE));
+
+    graph.add_pass("first", |pass| {
+        pass.write(chain[0]);
+        |_| {}
+    });
+
+    for i in 1..chain.len() {
+        graph.add_pass("effect", |pass| {
+            pass.read(chain[i - 1]).write(chain[i]);
+            |_| {}
+        });
+    }
+
+    graph.add_pass("present", |pass| {
+        pass.read(chain[3]).side_effects();
+        |_| {}
+    });
+
+    // like ping-pong textures, every step only needs its input and its output
+    assert_eq!(graph.compile().unwrap().transient_slots(), 2);
+}
+
+#[test]
+fn transients_with_different_descriptions_are_not_aliased() {
+    let mut graph = RenderGraph::new();
+
+    let full = graph.create_texture("full", TEXTURE);
+    let half = graph.create_texture(
+        "half",
+        TransientTexture {
+            width: 32,
+            height: 32,
+            ..TEXTURE
+        },
+    );
+
+    graph.add_pass("full", |pass| {
+        pass.write(full);
+        |_| {}
+    });
+    graph.add_pass("downsample", |pass| {
+        pass.read(full).write(half);
+        |_| {}
+    });
+    graph.add_pass("present", |pass| {
+        pass.read(half).side_effects();
+        |_| {}
+    });
+
+    assert_eq!(graph.compile().unwrap().transient_slots(), 2);
+}
+
+#[test]
+fn dot_describes_passes_and_resources() {
+    let mut graph = RenderGraph::new();
+
+    let texture = graph.create_texture("scene", TEXTURE);
+
+    graph.add_pass("draw", |pass| {
+        pass.write(texture);
+        |_| {}
+    });
+    graph.add_pass("unused", |pass| {
+        pass.read(texture);
+        |_| {}
+    });
+
+    let dot = graph.to_dot();
+
+    assert!(dot.starts_with("digraph render_graph {"));
+    assert!(dot.contains(r#"pass0 [label="draw", shape=box, style=dashed];"#));
+    assert!(dot.contains(r#"resource0 [label="scene", shape=ellipse, style=dashed];"#));
+    assert!(dot.contains("pass0 -> resource0;"));
+    assert!(dot.contains("resource0 -> pass1;"));
+}
+
+#[test]
+fn renderer_dumps_its_graph() {
+    let Some(mut renderer) = headless_renderer(PhysicalSize::new(32, 32)) else {
+        return;
+    };
+
+    renderer.post_settings.fxaa.enabled = true;
+    renderer.post_settings.vignette.enabled = true;
+    renderer.request_graph_dump();
+
+    render(&mut renderer, &fixed_camera());
+
+    let dot = renderer.take_graph_dump().unwrap();
+
+    for name in ["main", "tonemap", "FXAA", "Vignette", "frame", "hdr"] {
+        assert!(
+            dot.contains(&format!("label={name:?}")),
+            "{name} is missing from:\n{dot}"
+        );
+    }
+
+    assert!(renderer.take_graph_dump().is_none());
+}
@@ -0,0 +1,331 @@
+//! Offline validation and reflection of the shaders in `assets/`, checking them against the
+//! pipelines using them without needing a GPU.
+
+use std::{
+    num::NonZeroU64,
+    path::{Path, PathBuf},
+};
+
+use glam::Mat4;
+use gpu_template::renderer::{
+    instance::MeshInstance,
+    mesh::{Vertex, VertexLayout},
+    particles::Particle,
+    post::PostEffect,
+    shaders::{
+        ShaderKind, Shaders,
+        preprocessor::{Preprocessor, ShaderFiles},
+        reflection::ShaderReflection,
+    },
+};
+use naga::{
+    Binding, Module, Scalar, ShaderStage, TypeInner, VectorSize,
+    valid::{Capabilities, ValidationFlags, Validator},
+};
+use wgpu::{
+    BindGroupLayoutEntry, BindingType, BufferBindingType, SamplerBindingType, ShaderStages,
+    TextureSampleType, TextureViewDimension, VertexAttribute, vertex_attr_array,
+};
+
+/// Returns the path of the `assets/` directory.
+fn assets_directory() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("assets")
+}
+
+/// Loads, preprocesses and validates one of the shaders in `assets/`.
+fn load(kind: ShaderKind) -> (Module, ShaderReflection) {
+    let files = ShaderFiles::Directory(assets_directory());
+    let (shader, reflection) = Shaders::load(kind, &files).unwrap_or_else(|e| panic!("{e}"));
+    let (module, _) = Shaders::validate(kind, &shader).unwrap();
+
+    (module, reflection)
+}
+
+/// Returns the vertex buffer attributes the pipelines using the given shader are created with.
+fn vertex_attributes(kind: ShaderKind) -> Vec<VertexAttribute> {
+    match kind {
+        ShaderKind::Mesh => [Vertex::ATTRIBUTES, MeshInstance::ATTRIBUTES].concat(),
+        ShaderKind::Particle => Particle::ATTRIBUTES.to_vec(),
+        ShaderKind::Blit
+        | ShaderKind::Tonemap
+        | ShaderKind::Bloom
+        | ShaderKind::Post
+        | ShaderKind::ParticleSimulation => vec![],
+    }
+}
+
+/// Returns the fragment entry points the pipelines using the given shader are created with.
+///
+/// Render pipelines always use the `vs_main` vertex entry point alongside them.
+fn fragment_entry_points(kind: ShaderKind) -> Vec<&'static str> {
+    match kind {
+        ShaderKind::Mesh | ShaderKind::Blit | ShaderKind::Tonemap | ShaderKind::Particle => {
+            vec!["fs_main"]
+        }
+        ShaderKind::Bloom => vec!["fs_prefilter", "fs_downsample", "fs_upsample"],
+        ShaderKind::Post => PostEffect::ALL.map(PostEffect::entry_point).to_vec(),
+        ShaderKind::ParticleSimulation => vec![],
+    }
+}
+
+/// Returns the compute entry points the pipelines using the given shader are created with.
+fn compute_entry_points(kind: ShaderKind) -> Vec<&'static str> {
+    match kind {
+        ShaderKind::ParticleSimulation => vec!["cs_main"],
+        _ => vec![],
+    }
+}
+
+#[test]
+fn every_asset_validates() {
+    let files = ShaderFiles::Directory(assets_directory());
+
+    let names = std::fs::read_dir(assets_directory())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|name| name.ends_with(".wgsl"))
+        .collect::<Vec<_>>();
+
+    assert!(names.len() >= ShaderKind::ALL.len());
+
+    // files which are only included must be valid on their own too
+    for name in names {
+        let shader = Preprocessor::new(&files)
+            .process(&name)
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        let module = naga::front::wgsl::parse_str(&shader.source).unwrap_or_else(|e| {
+            panic!(
+                "{}",
+                shader.describe_error(e.message(), e.location(&shader.source))
+            )
+        });
+
+        if let Err(e) =
+            Validator::new(ValidationFlags::all(), Capabilities::all()).validate(&module)
+        {
+            panic!(
+                "{}",
+                shader.describe_error(&e.as_inner().to_string(), e.location(&shader.source))
+            );
+        }
+    }
+}
+
+#[test]
+fn embedded_shaders_match_assets() {
+    for kind in ShaderKind::ALL {
+        let embedded =
+            Shaders::load(kind, &ShaderFiles::Embedded).unwrap_or_else(|e| panic!("{e}"));
+        let on_disk = Shaders::load(kind, &ShaderFiles::Directory(assets_directory())).unwrap();
+
+        assert_eq!(embedded.0.source, on_disk.0.source, "{kind:?}");
+    }
+}
+
+#[test]
+fn entry_points_exist() {
+    for kind in ShaderKind::ALL {
+        let (module, _) = load(kind);
+
+        let entry_point = |name: &str, stage: ShaderStage| {
+            module
+                .entry_points
+                .iter()
+                .find(|entry_point| entry_point.name == name && entry_point.stage == stage)
+                .unwrap_or_else(|| panic!("{kind:?} is missing the {stage:?} entry point {name}"))
+        };
+
+        for name in compute_entry_points(kind) {
+            entry_point(name, ShaderStage::Compute);
+        }
+
+        let fragment_entry_points = fragment_entry_points(kind);
+
+        if !fragment_entry_points.is_empty() {
+            entry_point("vs_main", ShaderStage::Vertex);
+        }
+
+        for name in fragment_entry_points {
+            let fragment = entry_point(name, ShaderStage::Fragment);
+
+            // every pipeline writes a vec4<f32> into a single color target
+            let result = fragment.function.result.as_ref().unwrap();
+
+            assert!(
+                matches!(result.binding, Some(Binding::Location { location: 0, .. })),
+                "{kind:?} must output to location 0 from {name}"
+            );
+            assert!(matches!(
+                module.types[result.ty].inner,
+                TypeInner::Vector {
+                    size: VectorSize::Quad,
+                    scalar: Scalar::F32,
+                }
+            ));
+        }
+    }
+}
+
+#[test]
+fn vertex_inputs_match_vertex_buffers() {
+    for kind in ShaderKind::ALL {
+        let (_, reflection) = load(kind);
+
+        if let Err(e) = reflection.check_vertex_attributes(&vertex_attributes(kind)) {
+            panic!("{kind:?}: {e}");
+        }
+    }
+
+    // the mesh shader reads every attribute of its vertices and instances, which come from two
+    // buffers and so don't share offsets
+    let (_, reflection) = load(ShaderKind::Mesh);
+    let inputs = |attributes: &[VertexAttribute]| {
+        attributes
+            .iter()
+            .map(|attribute| (attribute.shader_location, attribute.format))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        inputs(&reflection.vertex_attributes),
+        inputs(&vertex_attributes(ShaderKind::Mesh))
+    );
+}
+
+#[test]
+fn vertex_inputs_must_be_provided() {
+    let (_, reflection) = load(ShaderKind::Mesh);
+
+    assert!(reflection.check_vertex_attributes(&[]).is_err());
+    assert!(
+        reflection
+            .check_vertex_attributes(
+                &vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x3, 3 => Float32x3]
+            )
+            .is_err()
+    );
+    // the instances are read from their own buffer, which must be provided too
+    assert!(
+        reflection
+            .check_vertex_attributes(Vertex::ATTRIBUTES)
+            .is_err()
+    );
+}
+
+#[test]
+fn bind_groups_are_reflected() {
+    let uniform = |size: usize| BindingType::Buffer {
+        ty: BufferBindingType::Uniform,
+        has_dynamic_offset: false,
+        min_binding_size: NonZeroU64::new(size as u64),
+    };
+
+    let texture = BindingType::Texture {
+        sample_type: TextureSampleType::Float { filterable: true },
+        view_dimension: TextureViewDimension::D2,
+        multisampled: false,
+    };
+
+    let sampler = BindingType::Sampler(SamplerBindingType::Filtering);
+
+    let entries = |entries: &[(ShaderStages, BindingType)]| {
+        entries
+            .iter()
+            .enumerate()
+            .map(|(binding, (visibility, ty))| BindGroupLayoutEntry {
+                binding: binding as u32,
+                visibility: *visibility,
+                ty: *ty,
+                count: None,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let (_, mesh) = load(ShaderKind::Mesh);
+    let (_, blit) = load(ShaderKind::Blit);
+    let (_, tonemap) = load(ShaderKind::Tonemap);
+    let (_, bloom) = load(ShaderKind::Bloom);
+    let (_, post) = load(ShaderKind::Post);
+    let (_, particle) = load(ShaderKind::Particle);
+    let (_, particle_simulation) = load(ShaderKind::ParticleSimulation);
+
+    assert_eq!(
+        mesh.bind_groups,
+        [
+            entries(&[(ShaderStages::VERTEX, uniform(size_of::<Mat4>()))]),
+            entries(&[
+                (ShaderStages::FRAGMENT, uniform(48)),
+                (ShaderStages::FRAGMENT, texture),
+                (ShaderStages::FRAGMENT, texture),
+                (ShaderStages::FRAGMENT, sampler),
+            ]),
+        ]
+    );
+
+    assert_eq!(
+        blit.bind_groups,
+        [entries(&[
+            (ShaderStages::FRAGMENT, texture),
+            (ShaderStages::FRAGMENT, sampler),
+        ])]
+    );
+
+    assert_eq!(
+        tonemap.bind_groups,
+        [entries(&[
+            (ShaderStages::FRAGMENT, texture),
+            (ShaderStages::FRAGMENT, sampler),
+            (ShaderStages::FRAGMENT, uniform(12)),
+        ])]
+    );
+
+    assert_eq!(
+        bloom.bind_groups,
+        [entries(&[
+            (ShaderStages::FRAGMENT, texture),
+            (ShaderStages::FRAGMENT, sampler),
+            (ShaderStages::FRAGMENT, uniform(8)),
+        ])]
+    );
+
+    assert_eq!(
+        post.bind_groups,
+        [entries(&[
+            (ShaderStages::FRAGMENT, texture),
+            (ShaderStages::FRAGMENT, sampler),
+            (ShaderStages::FRAGMENT, uniform(28)),
+            (
+                ShaderStages::FRAGMENT,
+                BindingType::Texture {
+                    sample_type: TextureSampleType::Float { filterable: true },
+                    view_dimension: TextureViewDimension::D3,
+                    multisampled: false,
+                },
+            ),
+        ])]
+    );
+
+    assert_eq!(
+        particle.bind_groups,
+        [
+            entries(&[(ShaderStages::VERTEX, uniform(size_of::<Mat4>()))]),
+            entries(&[(ShaderStages::VERTEX, uniform(128))]),
+        ]
+    );
+
+    assert_eq!(
+        particle_simulation.bind_groups,
+        [entries(&[
+            (
+                ShaderStages::COMPUTE,
+                BindingType::Buffer {
+                    ty: BufferBindingType::Storage { read_only: false },
+                    has_dynamic_offset: false,
+                    min_binding_size: NonZeroU64::new(size_of::<Particle>() as u64),
+                },
+            ),
+            (ShaderStages::COMPUTE, uniform(128)),
+        ])]
+    );
+}
@@ -0,0 +1,113 @@
+//! Tests for preprocessing and validating the shaders in `tests/assets/shaders/` and `assets/`.
+
+use std::path::Path;
+
+use gpu_template::renderer::shaders::{
+    ShaderKind, Shaders,
+    preprocessor::{Preprocessor, ShaderFiles},
+};
+
+/// Returns the files of the test shaders.
+fn test_files() -> ShaderFiles {
+    ShaderFiles::Directory(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/assets/shaders"))
+}
+
+#[test]
+fn includes_are_pasted_once() {
+    let shader = Preprocessor::new(&test_files())
+        .process("includes.wgsl")
+        .unwrap();
+
+    assert_eq!(shader.source.matches("const SCALE").count(), 1);
+    assert!(shader.source.contains("fn scaled"));
+
+    naga::front::wgsl::parse_str(&shader.source).unwrap();
+}
+
+#[test]
+fn lines_map_back_to_their_files() {
+    let shader = Preprocessor::new(&test_files())
+        .process("includes.wgsl")
+        .unwrap();
+
+    let location = |line| {
+        let location = shader.location(line).unwrap();
+        (location.file.to_string(), location.line)
+    };
+
+    // the directive lines themselves are removed
+    assert_eq!(location(1), ("common.wgsl".to_owned(), 1));
+    assert_eq!(location(2), ("includes.wgsl".to_owned(), 3));
+    assert_eq!(location(3), ("includes.wgsl".to_owned(), 4));
+    assert!(shader.location(0).is_none());
+}
+
+#[test]
+fn features_select_branches() {
+    let files = test_files();
+
+    let plain = Preprocessor::new(&files).process("features.wgsl").unwrap();
+    let doubled = Preprocessor::new(&files)
+        .define("DOUBLE")
+        .process("features.wgsl")
+        .unwrap();
+
+    assert!(plain.source.contains("FACTOR: f32 = 1.0"));
+    assert!(!plain.source.contains("FACTOR: f32 = 2.0"));
+    assert!(doubled.source.contains("FACTOR: f32 = 2.0"));
+    assert!(!doubled.source.contains("FACTOR: f32 = 1.0"));
+
+    // `#define` within the shader enables features for the lines after it
+    assert!(plain.source.contains("SIGN: f32 = -1.0"));
+    assert!(!plain.source.contains("SIGN: f32 = 1.0"));
+}
+
+#[test]
+fn unclosed_conditionals_are_rejected() {
+    let error = Preprocessor::new(&test_files())
+        .process("unclosed.wgsl")
+        .unwrap_err();
+
+    assert_eq!(
+        error.to_string().split(": ").next(),
+        Some("unclosed.wgsl:2")
+    );
+}
+
+#[test]
+fn missing_includes_point_at_the_directive() {
+    let error = Preprocessor::new(&test_files())
+        .process("missing_include.wgsl")
+        .unwrap_err();
+
+    let location = error.location.unwrap();
+
+    assert_eq!(&*location.file, "missing_include.wgsl");
+    assert_eq!(location.line, 2);
+    assert!(error.message.contains("does_not_exist.wgsl"));
+}
+
+#[test]
+fn compile_errors_point_at_the_original_file() {
+    let shader = Preprocessor::new(&test_files())
+        .process("includes_broken.wgsl")
+        .unwrap();
+
+    let error = naga::front::wgsl::parse_str(&shader.source).unwrap_err();
+    let message = shader.describe_error(error.message(), error.location(&shader.source));
+
+    assert!(
+        message.starts_with("broken_include.wgsl:2:"),
+        "unexpected error: {message}"
+    );
+    assert!(message.contains("missing_value"));
+}
+
+#[test]
+fn embedded_shaders_are_valid() {
+    for kind in ShaderKind::ALL {
+        if let Err(e) = Shaders::load(kind, &ShaderFiles::Embedded) {
+            panic!("{e}");
+        }
+    }
+}
@@ -0,0 +1,39 @@
+//! Tests for choosing the configuration of render targets.
+
+use gpu_template::renderer::target::RenderTarget;
+use wgpu::TextureFormat;
+
+#[test]
+fn srgb_formats_are_preferred() {
+    let supported = [TextureFormat::Bgra8Unorm, TextureFormat::Bgra8UnormSrgb];
+
+    assert_eq!(
+        RenderTarget::choose_format(&supported, None),
+        Some(TextureFormat::Bgra8UnormSrgb)
+    );
+}
+
+#[test]
+fn supported_preferences_are_used() {
+    let supported = [TextureFormat::Bgra8UnormSrgb, TextureFormat::Rgba16Float];
+
+    assert_eq!(
+        RenderTarget::choose_format(&supported, Some(TextureFormat::Rgba16Float)),
+        Some(TextureFormat::Rgba16Float)
+    );
+    assert_eq!(
+        RenderTarget::choose_format(&supported, Some(TextureFormat::Rgba8Unorm)),
+        Some(TextureFormat::Bgra8UnormSrgb)
+    );
+}
+
+#[test]
+fn falls_back_to_the_first_format() {
+    let supported = [TextureFormat::Rgb10a2Unorm, TextureFormat::Bgra8Unorm];
+
+    assert_eq!(
+        RenderTarget::choose_format(&supported, None),
+        Some(TextureFormat::Rgb10a2Unorm)
+    );
+    assert_eq!(RenderTarget::choose_format(&[], None), None);
+}